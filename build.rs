@@ -90,8 +90,41 @@ fn main() -> Result<()> {
         "static CHAMP_NAMES: phf::Map<&'static str, &'static str> = {map};"
     )?;
 
+    // Bans only have the ID, not the name
+    let mut builder = phf_codegen::Map::new();
+    for champ in champs.as_array().unwrap() {
+        let id = champ["id"].as_i64().unwrap();
+        let name = champ["name"].as_str().unwrap();
+        builder.entry(i32::try_from(id).unwrap(), &format!("{name:?}"));
+    }
+    let map = builder.build();
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("codegen-champ-names-by-id.rs");
+    let mut file = BufWriter::new(File::create(path)?);
+    // Pedantic does fire on generated code that's not a proc-macro.
+    writeln!(&mut file, "#[allow(clippy::unreadable_literal)]")?;
+    writeln!(
+        &mut file,
+        "static CHAMP_NAMES_BY_ID: phf::Map<i32, &'static str> = {map};"
+    )?;
+
     let items: Value =
         fetch_json("http://cdn.merakianalytics.com/riot/lol/resources/latest/en-US/items.json")?;
+    let mut builder = phf_codegen::Map::new();
+    for (item_id, info) in items.as_object().unwrap() {
+        if let Some(name) = info["name"].as_str() {
+            builder.entry(item_id.parse::<i32>()?, &format!("{name:?}"));
+        }
+    }
+    let map = builder.build();
+    let path = Path::new(&env::var("OUT_DIR").unwrap()).join("codegen-item-names.rs");
+    let mut file = BufWriter::new(File::create(path)?);
+    // Pedantic does fire on generated code that's not a proc-macro.
+    writeln!(&mut file, "#[allow(clippy::unreadable_literal)]")?;
+    writeln!(
+        &mut file,
+        "static ITEM_NAMES: phf::Map<i32, &'static str> = {map};"
+    )?;
+
     let mut builder = phf_codegen::Map::new();
     let ranks = items
         .as_object()
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// A coach's notes and tags on one player's side of a match.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct MatchAnnotation {
+    pub note: String,
+    pub tags: Vec<String>,
+}
+
+impl MatchAnnotation {
+    pub fn id(match_id: &str, puuid: &str) -> String {
+        format!("{match_id}/{puuid}")
+    }
+
+    /// Builds an annotation from a note and comma separated tags, dropping
+    /// empty and repeated tags.
+    pub fn parse(note: &str, tags: &str) -> Self {
+        let mut unique_tags: Vec<String> = vec![];
+        for tag in tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+            if !unique_tags
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(tag))
+            {
+                unique_tags.push(tag.to_string());
+            }
+        }
+        Self {
+            note: note.trim().to_string(),
+            tags: unique_tags,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.note.is_empty() && self.tags.is_empty()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags
+            .iter()
+            .any(|existing| existing.eq_ignore_ascii_case(tag))
+    }
+}
+
+/// A game left out of a player's stats by hand, e.g. because they were
+/// trying out something they'll never play again.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ExcludedGame {
+    pub reason: String,
+}
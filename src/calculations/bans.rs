@@ -0,0 +1,167 @@
+use super::{champion_name_by_id, get_player, matches_by_role_enemy, percentage};
+use crate::{
+    riot_api::json::{Match, Role},
+    State,
+};
+use itertools::Itertools;
+use ordered_float::OrderedFloat;
+use std::collections::{HashMap, HashSet};
+
+// Fewer games than this against a champion doesn't say much.
+const MIN_BAN_SAMPLE: u32 = 3;
+const MAX_RECOMMENDED_BANS: usize = 5;
+
+#[derive(Clone, Debug)]
+pub struct BanCount {
+    pub champion: String,
+    pub games: u32,
+    pub percentage: f64,
+    /// Whether the player played this champion themselves in these games
+    pub in_own_pool: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct LaneOpponentRecord {
+    pub role: Role,
+    pub champion: String,
+    pub games: u32,
+    pub wins: u32,
+    pub winrate: f64,
+    /// How many more games were lost than the player's overall winrate
+    /// predicts
+    pub losses_above_expected: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct BanAnalysis {
+    pub games: u32,
+    pub wins: u32,
+    pub winrate: f64,
+    pub banned_by_enemy: Vec<BanCount>,
+    pub banned_by_team: Vec<BanCount>,
+    pub lane_opponents: Vec<LaneOpponentRecord>,
+    pub recommended_bans: Vec<LaneOpponentRecord>,
+}
+
+fn losses_above_expected(games: u32, wins: u32, winrate: f64) -> f64 {
+    let expected_losses = f64::from(games) * (100.0 - winrate) / 100.0;
+    f64::from(games - wins) - expected_losses
+}
+
+fn count_bans(
+    matches: &[&Match],
+    puuid: &str,
+    own_team: bool,
+    own_pool: &HashSet<&str>,
+) -> Vec<BanCount> {
+    let games = u32::try_from(matches.len()).unwrap();
+    matches
+        .iter()
+        .flat_map(|m| {
+            let team_id = get_player(m, puuid).team_id;
+            m.info
+                .teams
+                .iter()
+                .filter(move |team| (team.id == team_id) == own_team)
+                .flat_map(|team| &team.bans)
+                // -1 is no ban
+                .filter(|ban| ban.champion_id >= 0)
+                .map(|ban| ban.champion_id)
+                // The same champion can't be banned twice in a game, but
+                // better safe than sorry
+                .unique()
+        })
+        .counts()
+        .into_iter()
+        .map(|(champion_id, count)| {
+            let champion = champion_name_by_id(champion_id);
+            let count = u32::try_from(count).unwrap();
+            BanCount {
+                in_own_pool: own_pool.contains(champion.as_str()),
+                champion,
+                games: count,
+                percentage: percentage(count, games),
+            }
+        })
+        .sorted_by(|a, b| b.games.cmp(&a.games).then(a.champion.cmp(&b.champion)))
+        .collect()
+}
+
+/// Looks at which champions get banned in the player's games, and which lane
+/// opponents they lose to when those aren't banned.
+pub fn ban_analysis(state: &State, matches: &[&Match], puuid: &str) -> BanAnalysis {
+    let games = u32::try_from(matches.len()).unwrap();
+    let wins = u32::try_from(matches.iter().filter(|m| get_player(m, puuid).win).count()).unwrap();
+    let winrate = percentage(wins, games);
+    let own_pool = matches
+        .iter()
+        .map(|m| get_player(m, puuid).champion_name.as_str())
+        .collect::<HashSet<_>>();
+    let lane_opponents = matches_by_role_enemy(state, matches, puuid)
+        .into_iter()
+        .flat_map(|(role, champ_matches)| {
+            champ_matches.into_iter().map(move |(champion, matches)| {
+                let games = u32::try_from(matches.len()).unwrap();
+                let wins =
+                    u32::try_from(matches.iter().filter(|m| get_player(m, puuid).win).count())
+                        .unwrap();
+                LaneOpponentRecord {
+                    role,
+                    champion,
+                    games,
+                    wins,
+                    winrate: percentage(wins, games),
+                    losses_above_expected: losses_above_expected(games, wins, winrate),
+                }
+            })
+        })
+        .sorted_by_key(|record| std::cmp::Reverse(OrderedFloat(record.losses_above_expected)))
+        .collect::<Vec<_>>();
+    // A champion can be a lane opponent in more than one role, but a ban
+    // covers all of them
+    let mut per_champion: HashMap<&str, (u32, u32)> = HashMap::new();
+    for record in &lane_opponents {
+        let entry = per_champion.entry(record.champion.as_str()).or_default();
+        entry.0 += record.games;
+        entry.1 += record.wins;
+    }
+    let recommended_bans = lane_opponents
+        .iter()
+        .filter_map(|record| {
+            let (games, wins) = per_champion.remove(record.champion.as_str())?;
+            let losses_above_expected = losses_above_expected(games, wins, winrate);
+            (games >= MIN_BAN_SAMPLE && losses_above_expected > 0.0).then(|| LaneOpponentRecord {
+                games,
+                wins,
+                winrate: percentage(wins, games),
+                losses_above_expected,
+                ..record.clone()
+            })
+        })
+        .sorted_by_key(|record| std::cmp::Reverse(OrderedFloat(record.losses_above_expected)))
+        .take(MAX_RECOMMENDED_BANS)
+        .collect();
+    BanAnalysis {
+        games,
+        wins,
+        winrate,
+        banned_by_enemy: count_bans(matches, puuid, false, &own_pool),
+        banned_by_team: count_bans(matches, puuid, true, &own_pool),
+        lane_opponents,
+        recommended_bans,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::losses_above_expected;
+    use test_case::test_case;
+
+    #[test_case(10, 5, 50.0, 0.0)]
+    #[test_case(10, 2, 50.0, 3.0)]
+    #[test_case(4, 4, 50.0, -2.0)]
+    #[test_case(5, 0, 60.0, 3.0)]
+    fn test_losses_above_expected(games: u32, wins: u32, winrate: f64, expected: f64) {
+        assert!((losses_above_expected(games, wins, winrate) - expected).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,240 @@
+use super::{
+    custom_metric, exclusion_reason, metric, metrics, GameMetrics, GroupStats, SOLO_QUEUE_ID,
+};
+use crate::{normalize_champion_name, riot_api::json::Role, InnerState, State, CHAMP_NAMES};
+use dashmap::DashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+// Fewer games than this say too little about how a role or champion is
+// usually played.
+const MIN_BASELINE_GAMES: usize = 20;
+
+/// Everyone's side of the stored games in a role, keyed by the role and the
+/// champion and patch if any, along with whose side it is. Filled in as
+/// baselines are asked for, and cleared whenever what they are made of
+/// changes.
+pub type BaselineCache =
+    DashMap<(Role, Option<String>, Option<String>), Arc<Vec<(String, GameMetrics)>>>;
+
+/// Forgets every cached baseline game, for when matches, timelines, custom
+/// metrics or hand-picked exclusions change.
+pub fn invalidate_baselines(state: &InnerState) {
+    state.baselines.clear();
+}
+
+/// Every stored game in `role`, on `champion` and `patch` if given, from
+/// the cache if we have them.
+fn baseline_games(
+    state: &State,
+    role: Role,
+    champion: Option<&str>,
+    patch: Option<&str>,
+) -> Arc<Vec<(String, GameMetrics)>> {
+    let key = (
+        role,
+        champion.map(ToString::to_string),
+        patch.map(ToString::to_string),
+    );
+    if let Some(games) = state.baselines.get(&key) {
+        return Arc::clone(&games);
+    }
+    let games = match patch {
+        // Cheaper than going through every match again
+        Some(patch) => baseline_games(state, role, champion, None)
+            .iter()
+            .filter(|(_, game)| game.patch == patch)
+            .cloned()
+            .collect(),
+        None => scan_games(state, role, champion),
+    };
+    let games = Arc::new(games);
+    state.baselines.insert(key, Arc::clone(&games));
+    games
+}
+
+fn scan_games(state: &State, role: Role, champion: Option<&str>) -> Vec<(String, GameMetrics)> {
+    let custom_metrics = custom_metric::load(state);
+    // The same match is stored once for every player in it we've looked at
+    let mut seen = HashSet::new();
+    let mut games = vec![];
+    for matches in &state.matches_per_puuid {
+        for m in matches.values() {
+            if m.info.queue_id != SOLO_QUEUE_ID || !seen.insert(m.metadata.match_id.clone()) {
+                continue;
+            }
+            let Some(timeline) = state.timeline_per_match.get(&m.metadata.match_id) else {
+                continue;
+            };
+            for participant in &m.info.participants {
+                if participant.team_position != role
+                    || champion.is_some_and(|champion| {
+                        normalize_champion_name(&participant.champion_name) != champion
+                    })
+                    || exclusion_reason(state, m, Some(&timeline), &participant.puuid).is_some()
+                {
+                    continue;
+                }
+                let mut game = metrics::game_metrics(m, &timeline, &participant.puuid);
+                game.custom =
+                    custom_metric::evaluate_all(&custom_metrics, m, &timeline, &participant.puuid);
+                games.push((participant.puuid.clone(), game));
+            }
+        }
+    }
+    games
+}
+
+fn others<'a>(games: &'a [(String, GameMetrics)], puuid: &str) -> Vec<&'a GameMetrics> {
+    games
+        .iter()
+        .filter(|(other, _)| other != puuid)
+        .map(|(_, game)| game)
+        .collect()
+}
+
+/// How everyone else in our stored matches did in a role, and on a champion
+/// if there is one, to rank a player's stats against.
+#[derive(Clone, Debug)]
+pub struct Baseline {
+    /// Like "mid Ahri on patch 14.20"
+    pub description: String,
+    pub games: usize,
+    /// Each metric's values in those games, sorted, for the metrics that
+    /// can be compared game by game
+    values: HashMap<String, Vec<f64>>,
+}
+
+/// Where `value` falls among `sorted`, from 0 to 100, as the share of values
+/// below it. Equal values count half.
+#[allow(clippy::cast_precision_loss)]
+fn percentile_rank(sorted: &[f64], value: f64) -> Option<f64> {
+    if sorted.is_empty() || !value.is_finite() {
+        return None;
+    }
+    let below = sorted.partition_point(|v| *v < value);
+    let equal = sorted.partition_point(|v| *v <= value) - below;
+    Some((below as f64 + equal as f64 / 2.0) / sorted.len() as f64 * 100.0)
+}
+
+/// "1st", "2nd", "72nd" and so on.
+pub(super) fn ordinal(n: u32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+impl Baseline {
+    /// The baseline for `puuid`'s stats in `role`, from the other players'
+    /// side of every stored match. Only the patch the player last played on
+    /// is used if there are enough games on it. Returns `None` if there
+    /// aren't enough games at all.
+    pub fn for_player(
+        state: &State,
+        puuid: &str,
+        role: Role,
+        champion: Option<&str>,
+    ) -> Option<Self> {
+        let patch = state
+            .matches_per_puuid
+            .get(puuid)?
+            .values()
+            .filter(|m| m.info.queue_id == SOLO_QUEUE_ID)
+            .max_by_key(|m| m.info.game_start_timestamp)?
+            .info
+            .patch();
+        let mut description = role.lowercase();
+        if let Some(champion) = champion.and_then(|champion| CHAMP_NAMES.get(champion)) {
+            description = format!("{description} {champion}");
+        }
+        let on_patch = baseline_games(state, role, champion, Some(&patch));
+        let every_patch = baseline_games(state, role, champion, None);
+        let mut games = others(&on_patch, puuid);
+        if games.len() >= MIN_BASELINE_GAMES {
+            description = format!("{description} on patch {patch}");
+        } else {
+            games = others(&every_patch, puuid);
+        }
+        if games.len() < MIN_BASELINE_GAMES {
+            return None;
+        }
+        let custom_metrics = custom_metric::load(state);
+        let values = metric::all_metrics(&custom_metrics)
+            .filter(|metric| metric.comparable_per_game())
+            .map(|metric| {
+                let mut values = games
+                    .iter()
+                    .filter_map(|game| metric.extract(game))
+                    .filter(|value| value.is_finite())
+                    .collect::<Vec<_>>();
+                values.sort_by(f64::total_cmp);
+                (metric.id().to_string(), values)
+            })
+            .collect();
+        Some(Self {
+            description,
+            games: games.len(),
+            values,
+        })
+    }
+
+    /// Sets the percentile of every metric in `group`, turned around for the
+    /// metrics where less is better, so that higher is always better.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn rank(&self, group: &mut GroupStats) {
+        for metric in &mut group.metrics {
+            metric.percentile = self
+                .values
+                .get(&metric.id)
+                .and_then(|values| percentile_rank(values, metric.value.number))
+                .map(|rank| {
+                    let rank = if metric.value.up_is_good {
+                        rank
+                    } else {
+                        100.0 - rank
+                    };
+                    // "100th percentile" would claim more than a sample can
+                    rank.round().clamp(1.0, 99.0) as u32
+                });
+        }
+        group.baseline = Some(format!(
+            "{} games of other players as {}",
+            self.games, self.description
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ordinal, percentile_rank};
+    use test_case::test_case;
+
+    #[test_case(&[1.0, 2.0, 3.0, 4.0], 5.0, Some(100.0))]
+    #[test_case(&[1.0, 2.0, 3.0, 4.0], 0.0, Some(0.0))]
+    #[test_case(&[1.0, 2.0, 3.0, 4.0], 2.5, Some(50.0))]
+    #[test_case(&[1.0, 2.0, 2.0, 3.0], 2.0, Some(50.0))]
+    #[test_case(&[], 2.0, None)]
+    #[test_case(&[1.0], f64::NAN, None)]
+    fn test_percentile_rank(sorted: &[f64], value: f64, expected: Option<f64>) {
+        assert_eq!(percentile_rank(sorted, value), expected);
+    }
+
+    #[test_case(1, "1st")]
+    #[test_case(2, "2nd")]
+    #[test_case(3, "3rd")]
+    #[test_case(11, "11th")]
+    #[test_case(12, "12th")]
+    #[test_case(22, "22nd")]
+    #[test_case(72, "72nd")]
+    #[test_case(99, "99th")]
+    fn test_ordinal(n: u32, expected: &str) {
+        assert_eq!(ordinal(n), expected);
+    }
+}
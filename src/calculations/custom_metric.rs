@@ -0,0 +1,329 @@
+use super::{
+    expression::{Environment, EvaluationError, Expression},
+    get_player, get_team,
+    metric::{format_number, summarize, Metric},
+    metrics::GameMetrics,
+    timeline_get_player_id, NumberWithOptionalDelta, Summary,
+};
+use crate::{riot_api::json, State};
+use chrono::TimeDelta;
+use log::warn;
+use std::collections::HashMap;
+
+/// Functions of a minute that custom metrics can use, besides `min` and
+/// `max`, with what they return.
+pub const FUNCTIONS: &[(&str, &str)] = &[
+    ("kills_before(minute)", "Kills before that minute"),
+    ("deaths_before(minute)", "Deaths before that minute"),
+    ("assists_before(minute)", "Assists before that minute"),
+    (
+        "wards_placed_before(minute)",
+        "Wards placed before that minute",
+    ),
+    ("gold_at(minute)", "Total gold at that minute"),
+    (
+        "cs_at(minute)",
+        "Minions and monsters killed by that minute",
+    ),
+    ("xp_at(minute)", "Experience at that minute"),
+];
+
+/// A user-defined metric, ready to be evaluated.
+#[derive(Debug)]
+pub struct CustomMetric {
+    id: String,
+    name: String,
+    expression: Expression,
+    summary: Summary,
+    up_is_good: bool,
+    percent: bool,
+    /// Where its values are in `GameMetrics::custom`
+    index: usize,
+}
+
+impl Metric for CustomMetric {
+    fn id(&self) -> &str {
+        &self.id
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn extract(&self, game: &GameMetrics) -> Option<f64> {
+        game.custom.get(self.index).copied().flatten()
+    }
+    fn aggregate(&self, games: &[GameMetrics]) -> NumberWithOptionalDelta {
+        summarize(self, games, self.summary)
+    }
+    fn up_is_good(&self) -> bool {
+        self.up_is_good
+    }
+    fn format(&self, value: f64) -> String {
+        format_number(value, self.percent)
+    }
+}
+
+/// The custom metrics as they're currently defined. Ones whose expression
+/// doesn't parse, e.g. after editing the file by hand, are left out.
+pub fn load(state: &State) -> Vec<CustomMetric> {
+    state
+        .custom_metrics
+        .list()
+        .into_iter()
+        .filter_map(
+            |(id, definition)| match Expression::parse(&definition.expression) {
+                Ok(expression) => Some((id, definition, expression)),
+                Err(e) => {
+                    warn!("Skipping custom metric {id}: {e}");
+                    None
+                }
+            },
+        )
+        .enumerate()
+        .map(|(index, (id, definition, expression))| CustomMetric {
+            id: format!("custom_{id}"),
+            name: definition.name,
+            expression,
+            summary: definition.summary,
+            up_is_good: definition.up_is_good,
+            percent: definition.percent,
+            index,
+        })
+        .collect()
+}
+
+/// Turns `camelCase` into `snake_case`, to match the field names in our
+/// code.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// Every number and flag of the participant, under the same name as in
+/// `json::Participant`.
+fn participant_variables(player: &json::Participant) -> HashMap<String, f64> {
+    match serde_json::to_value(player) {
+        Ok(serde_json::Value::Object(fields)) => fields
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let value = match value {
+                    serde_json::Value::Number(number) => number.as_f64()?,
+                    serde_json::Value::Bool(flag) => f64::from(u8::from(flag)),
+                    _ => return None,
+                };
+                Some((snake_case(&name), value))
+            })
+            .collect(),
+        _ => HashMap::new(),
+    }
+}
+
+// Variables that aren't the participant's own numbers
+const GAME_VARIABLES: [&str; 3] = ["game_minutes", "team_kills", "team_gold_earned"];
+
+fn is_variable(name: &str) -> bool {
+    GAME_VARIABLES.contains(&name)
+        || participant_variables(&json::Participant::default()).contains_key(name)
+}
+
+fn is_function(name: &str, args: usize) -> bool {
+    args == 1
+        && FUNCTIONS.iter().any(|(signature, _)| {
+            signature
+                .split_once('(')
+                .is_some_and(|(function, _)| function == name)
+        })
+}
+
+/// The player's side of a single game, as seen by an expression.
+struct GameEnvironment<'a> {
+    variables: HashMap<String, f64>,
+    timeline: &'a json::Timeline,
+    player_id: usize,
+}
+
+impl<'a> GameEnvironment<'a> {
+    fn new(m: &json::Match, timeline: &'a json::Timeline, puuid: &str) -> Self {
+        let player = get_player(m, puuid);
+        let mut variables = participant_variables(player);
+        #[allow(clippy::cast_precision_loss)]
+        let game_minutes = m.info.game_duration.num_seconds() as f64 / 60.0;
+        variables.insert("game_minutes".to_string(), game_minutes);
+        let team = get_team(m, player);
+        variables.insert(
+            "team_kills".to_string(),
+            f64::from(team.iter().map(|p| p.kills).sum::<i32>()),
+        );
+        variables.insert(
+            "team_gold_earned".to_string(),
+            f64::from(team.iter().map(|p| p.gold_earned).sum::<i32>()),
+        );
+        Self {
+            variables,
+            timeline,
+            player_id: timeline_get_player_id(timeline, puuid),
+        }
+    }
+
+    fn events_before(&self, time: TimeDelta) -> impl Iterator<Item = &json::Event> {
+        self.timeline
+            .info
+            .frames
+            .iter()
+            .flat_map(|frame| &frame.events)
+            .filter(move |event| match event {
+                json::Event::ChampionKill(kill) => kill.timestamp < time,
+                json::Event::WardPlaced { timestamp, .. } => *timestamp < time,
+                _ => false,
+            })
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn count_before(&self, time: TimeDelta, matches: impl Fn(&json::Event) -> bool) -> f64 {
+        self.events_before(time)
+            .filter(|event| matches(event))
+            .count() as f64
+    }
+
+    fn frame_at(&self, time: TimeDelta) -> Result<&json::ParticipantFrame, EvaluationError> {
+        self.timeline
+            .info
+            .frames
+            .iter()
+            .find(|frame| frame.timestamp >= time)
+            .and_then(|frame| frame.participant_frames.get(&self.player_id))
+            .ok_or(EvaluationError::Unavailable)
+    }
+}
+
+impl Environment for GameEnvironment<'_> {
+    fn variable(&self, name: &str) -> Option<f64> {
+        self.variables.get(name).copied()
+    }
+
+    fn call(&self, name: &str, args: &[f64]) -> Result<f64, EvaluationError> {
+        let &[minute] = args else {
+            return Err(EvaluationError::UnknownFunction(
+                name.to_string(),
+                args.len(),
+            ));
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        let time = TimeDelta::milliseconds((minute * 60_000.0) as i64);
+        let id = self.player_id;
+        match name {
+            "kills_before" => Ok(self.count_before(time, |event| {
+                matches!(event, json::Event::ChampionKill(kill) if kill.killer_id == id)
+            })),
+            "deaths_before" => Ok(self.count_before(time, |event| {
+                matches!(event, json::Event::ChampionKill(kill) if kill.victim_id == id)
+            })),
+            "assists_before" => Ok(self.count_before(time, |event| {
+                matches!(event, json::Event::ChampionKill(kill)
+                    if kill.assisting_participant_ids.contains(&id))
+            })),
+            "wards_placed_before" => Ok(self.count_before(time, |event| {
+                matches!(event, json::Event::WardPlaced { creator_id, .. } if *creator_id == id)
+            })),
+            "gold_at" => Ok(f64::from(self.frame_at(time)?.total_gold)),
+            "cs_at" => {
+                let frame = self.frame_at(time)?;
+                Ok(f64::from(frame.minions_killed + frame.jungle_minions_killed))
+            }
+            "xp_at" => Ok(f64::from(self.frame_at(time)?.xp)),
+            _ => Err(EvaluationError::UnknownFunction(name.to_string(), 1)),
+        }
+    }
+}
+
+/// Every custom metric's value in a single game, in the same order as
+/// `metrics`. A metric has no value in a game if e.g. it divides by zero,
+/// or asks for minute 15 of a game that ended before that.
+pub fn evaluate_all(
+    metrics: &[CustomMetric],
+    m: &json::Match,
+    timeline: &json::Timeline,
+    puuid: &str,
+) -> Vec<Option<f64>> {
+    let environment = GameEnvironment::new(m, timeline, puuid);
+    metrics
+        .iter()
+        .map(|metric| {
+            metric
+                .expression
+                .evaluate(&environment)
+                .ok()
+                .filter(|value| value.is_finite())
+        })
+        .collect()
+}
+
+/// Checks an expression before it's saved, by parsing it, checking the
+/// names it uses and trying it on one of the games we have, if there are
+/// any. Returns the value in that game, if it has one.
+///
+/// # Errors
+///
+/// If the expression doesn't parse, or uses variables or functions that
+/// don't exist.
+pub fn try_expression(state: &State, expression: &str) -> Result<Option<f64>, String> {
+    let expression = Expression::parse(expression)?;
+    expression
+        .check_names(&is_variable, &is_function)
+        .map_err(|e| e.to_string())?;
+    let result = state.matches_per_puuid.iter().find_map(|matches| {
+        matches.values().find_map(|m| {
+            let timeline = state.timeline_per_match.get(&m.metadata.match_id)?;
+            Some(expression.evaluate(&GameEnvironment::new(m, &timeline, matches.key())))
+        })
+    });
+    match result {
+        Some(Ok(value)) => Ok(Some(value).filter(|value| value.is_finite())),
+        Some(Err(
+            e @ (EvaluationError::UnknownVariable(_) | EvaluationError::UnknownFunction(..)),
+        )) => Err(e.to_string()),
+        None | Some(Err(EvaluationError::DivisionByZero | EvaluationError::Unavailable)) => {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_function, is_variable, snake_case};
+    use test_case::test_case;
+
+    #[test_case("goldEarned", "gold_earned")]
+    #[test_case("timeCCingOthers", "time_c_cing_others")]
+    #[test_case("kills", "kills")]
+    fn test_snake_case(name: &str, expected: &str) {
+        assert_eq!(snake_case(name), expected);
+    }
+
+    #[test_case("kills", true)]
+    #[test_case("gold_earned", true)]
+    #[test_case("first_blood_kill", true ; "flag")]
+    #[test_case("game_minutes", true)]
+    #[test_case("team_gold_earned", true)]
+    #[test_case("champion_name", false ; "text")]
+    #[test_case("goldEarned", false ; "camel case")]
+    #[test_case("no_such_var", false)]
+    fn test_is_variable(name: &str, expected: bool) {
+        assert_eq!(is_variable(name), expected);
+    }
+
+    #[test_case("gold_at", 1, true)]
+    #[test_case("kills_before", 1, true)]
+    #[test_case("gold_at", 2, false)]
+    #[test_case("gold", 1, false)]
+    fn test_is_function(name: &str, args: usize, expected: bool) {
+        assert_eq!(is_function(name, args), expected);
+    }
+}
@@ -0,0 +1,166 @@
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter, Write};
+
+/// Which single number a metric is shown as. Averages get pulled around by a
+/// few extreme games, so most metrics use the median, but counts that are
+/// usually small, like kills, say more as an average.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Summary {
+    Mean,
+    Median,
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Summary::Mean => write!(f, "Average"),
+            Summary::Median => write!(f, "Median"),
+        }
+    }
+}
+
+/// How a metric is spread over the games.
+#[derive(Clone, Debug, Serialize)]
+pub struct Distribution {
+    pub count: usize,
+    pub min: f64,
+    pub p25: f64,
+    pub median: f64,
+    pub p75: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+}
+
+/// Percentile of sorted values, interpolating between the two closest ones.
+fn percentile(sorted: &[f64], fraction: f64) -> f64 {
+    #[allow(clippy::cast_precision_loss)]
+    let position = fraction * (sorted.len() - 1) as f64;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let lower = position.floor() as usize;
+    let upper = (lower + 1).min(sorted.len() - 1);
+    let weight = position - position.floor();
+    sorted[lower] + (sorted[upper] - sorted[lower]) * weight
+}
+
+impl Distribution {
+    pub fn from_samples(samples: &[f64]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable_by_key(|value| OrderedFloat(*value));
+        #[allow(clippy::cast_precision_loss)]
+        let count = sorted.len() as f64;
+        let mean = sorted.iter().sum::<f64>() / count;
+        // Sample standard deviation, there's no spread in a single game
+        let std_dev = if sorted.len() > 1 {
+            (sorted
+                .iter()
+                .map(|value| (value - mean).powi(2))
+                .sum::<f64>()
+                / (count - 1.0))
+                .sqrt()
+        } else {
+            0.0
+        };
+        Some(Self {
+            count: sorted.len(),
+            min: sorted[0],
+            p25: percentile(&sorted, 0.25),
+            median: percentile(&sorted, 0.5),
+            p75: percentile(&sorted, 0.75),
+            max: sorted[sorted.len() - 1],
+            mean,
+            std_dev,
+        })
+    }
+
+    pub fn tooltip(&self, summary: Summary) -> String {
+        format!(
+            "{summary} of {} games. Min {:.1}, 25% {:.1}, median {:.1}, 75% {:.1}, max {:.1}, average {:.1}, standard deviation {:.1}",
+            self.count,
+            self.min,
+            self.p25,
+            self.median,
+            self.p75,
+            self.max,
+            self.mean,
+            self.std_dev
+        )
+    }
+
+    /// A small inline SVG box plot, from min to max, with the box from the
+    /// 25th to the 75th percentile and a line at the median.
+    pub fn box_plot(&self, summary: Summary) -> String {
+        const WIDTH: f64 = 60.0;
+        const MARGIN: f64 = 2.0;
+        let range = self.max - self.min;
+        let x = |value: f64| {
+            if range <= 0.0 {
+                WIDTH / 2.0
+            } else {
+                MARGIN + (value - self.min) / range * (WIDTH - 2.0 * MARGIN)
+            }
+        };
+        let mut svg = String::new();
+        write!(
+            svg,
+            r#"<svg class="distribution" width="{WIDTH}" height="12" viewBox="0 0 {WIDTH} 12"><title>{}</title>"#,
+            self.tooltip(summary)
+        )
+        .unwrap();
+        write!(
+            svg,
+            r#"<line x1="{:.1}" y1="6" x2="{:.1}" y2="6"/>"#,
+            x(self.min),
+            x(self.max)
+        )
+        .unwrap();
+        write!(
+            svg,
+            r#"<rect x="{:.1}" y="2" width="{:.1}" height="8"/>"#,
+            x(self.p25),
+            x(self.p75) - x(self.p25)
+        )
+        .unwrap();
+        write!(
+            svg,
+            r#"<line class="median" x1="{0:.1}" y1="1" x2="{0:.1}" y2="11"/></svg>"#,
+            x(self.median)
+        )
+        .unwrap();
+        svg
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{percentile, Distribution};
+    use test_case::test_case;
+
+    #[test_case(&[1.0, 2.0, 3.0, 4.0, 5.0], 0.5, 3.0)]
+    #[test_case(&[1.0, 2.0, 3.0, 4.0], 0.5, 2.5)]
+    #[test_case(&[1.0, 2.0, 3.0, 4.0, 5.0], 0.25, 2.0)]
+    #[test_case(&[1.0, 2.0, 3.0, 4.0], 0.75, 3.25)]
+    #[test_case(&[7.0], 0.25, 7.0)]
+    fn test_percentile(sorted: &[f64], fraction: f64, expected: f64) {
+        assert!((percentile(sorted, fraction) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_distribution() {
+        // Same median, very different spread
+        let consistent = Distribution::from_samples(&[7.0, 7.0, 7.0, 7.0]).unwrap();
+        let swingy = Distribution::from_samples(&[10.0, 4.0, 10.0, 4.0]).unwrap();
+        assert!((consistent.median - 7.0).abs() < 1e-9);
+        assert!((swingy.median - 7.0).abs() < 1e-9);
+        assert!(consistent.std_dev.abs() < 1e-9);
+        assert!((swingy.std_dev - 12.0_f64.sqrt()).abs() < 1e-9);
+        assert!((swingy.min - 4.0).abs() < 1e-9);
+        assert!((swingy.max - 10.0).abs() < 1e-9);
+        assert!(Distribution::from_samples(&[]).is_none());
+    }
+}
@@ -0,0 +1,113 @@
+use super::{convert_stats, gather_stats, get_player, get_team, percentage, GroupStats};
+use crate::{riot_api::json::Match, State};
+use itertools::Itertools;
+
+// Fewer games than this together is more likely a coincidence than a duo.
+const MIN_DUO_GAMES: usize = 3;
+
+#[derive(Clone, Debug)]
+pub struct DuoStats {
+    pub game_name: String,
+    pub tag_line: String,
+    pub games_together: u32,
+    pub winrate_together: f64,
+    pub games_apart: u32,
+    pub winrate_apart: f64,
+    // The player's own stats, in the games together
+    pub stats: GroupStats,
+}
+
+impl DuoStats {
+    pub fn winrate_difference(&self) -> f64 {
+        self.winrate_together - self.winrate_apart
+    }
+}
+
+/// Finds teammates that show up in at least `MIN_DUO_GAMES` of `matches`, and
+/// how the player does with and without them. Sorted by games together,
+/// descending.
+pub fn find_duos(state: &State, matches: &[&Match], puuid: &str, title: &str) -> Vec<DuoStats> {
+    let teammates_per_game = matches
+        .iter()
+        .map(|m| {
+            let player = get_player(m, puuid);
+            get_team(m, player)
+                .into_iter()
+                .filter(|p| p.puuid != puuid)
+                .map(|p| p.puuid.as_str())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let total_wins = matches.iter().filter(|m| get_player(m, puuid).win).count();
+    recurring_teammates(&teammates_per_game, MIN_DUO_GAMES)
+        .into_iter()
+        .map(|(teammate_puuid, game_indices)| {
+            let together = game_indices
+                .iter()
+                .map(|&index| matches[index])
+                .collect::<Vec<_>>();
+            let wins_together = together.iter().filter(|m| get_player(m, puuid).win).count();
+            // Use the most recent name, in case they changed it
+            let teammate = together
+                .iter()
+                .max_by_key(|m| m.info.game_start_timestamp)
+                .map(|m| get_player(m, teammate_puuid))
+                .unwrap();
+            let games_together = u32::try_from(together.len()).unwrap();
+            let games_apart = u32::try_from(matches.len() - together.len()).unwrap();
+            let wins_together = u32::try_from(wins_together).unwrap();
+            let wins_apart = u32::try_from(total_wins).unwrap() - wins_together;
+            DuoStats {
+                game_name: teammate.riot_id_game_name.clone(),
+                tag_line: teammate.riot_id_tagline.clone(),
+                games_together,
+                winrate_together: percentage(wins_together, games_together),
+                games_apart,
+                winrate_apart: percentage(wins_apart, games_apart),
+                stats: convert_stats(title, gather_stats(state, &together, puuid), None),
+            }
+        })
+        .collect()
+}
+
+/// Takes the teammates in each game, and returns the teammates that are in
+/// at least `min_games` of them, with the indices of those games. Sorted by
+/// number of games, descending.
+fn recurring_teammates<'a>(
+    teammates_per_game: &[Vec<&'a str>],
+    min_games: usize,
+) -> Vec<(&'a str, Vec<usize>)> {
+    teammates_per_game
+        .iter()
+        .enumerate()
+        .flat_map(|(index, teammates)| teammates.iter().map(move |teammate| (*teammate, index)))
+        .into_group_map()
+        .into_iter()
+        .filter(|(_, games)| games.len() >= min_games)
+        // Ties are sorted by puuid, so the order is stable
+        .sorted_by(|(a_puuid, a_games), (b_puuid, b_games)| {
+            b_games.len().cmp(&a_games.len()).then(a_puuid.cmp(b_puuid))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::recurring_teammates;
+
+    #[test]
+    fn test_recurring_teammates() {
+        let teammates_per_game = vec![
+            vec!["a", "b", "c"],
+            vec!["a", "d"],
+            vec!["b", "a"],
+            vec!["e"],
+            vec!["b"],
+        ];
+        assert_eq!(
+            recurring_teammates(&teammates_per_game, 3),
+            vec![("a", vec![0, 1, 2]), ("b", vec![0, 2, 4])]
+        );
+        assert_eq!(recurring_teammates(&teammates_per_game, 4), vec![]);
+    }
+}
@@ -0,0 +1,141 @@
+use super::{get_player, get_team, timeline_get_player_id};
+use crate::{
+    annotation::MatchAnnotation,
+    from_str_value,
+    riot_api::json::{self, Match, Role},
+    State,
+};
+use itertools::Itertools;
+use std::fmt::{self, Display, Formatter};
+
+// Standing in the same spot for this many frames, i.e. minutes, means AFK.
+const AFK_FRAMES: usize = 5;
+
+/// Why a game doesn't say much about how a player plays.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ExclusionReason {
+    Remake,
+    Leaver,
+    RoleSwap,
+    Manual,
+}
+
+impl Display for ExclusionReason {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ExclusionReason::Remake => write!(f, "remake"),
+            ExclusionReason::Leaver => write!(f, "AFK or leaving teammate"),
+            ExclusionReason::RoleSwap => write!(f, "played another role"),
+            ExclusionReason::Manual => write!(f, "left out by hand"),
+        }
+    }
+}
+
+/// Whether someone stood still for `AFK_FRAMES` frames in a row.
+fn is_afk(positions: &[json::Point]) -> bool {
+    positions
+        .iter()
+        .chunk_by(|position| **position)
+        .into_iter()
+        .any(|(_, run)| run.count() >= AFK_FRAMES)
+}
+
+/// Whether Riot thinks the player mostly played somewhere else than the role
+/// they were assigned.
+fn is_role_swap(team_position: Role, individual_position: &str) -> bool {
+    // "Invalid" when Riot can't tell
+    from_str_value::<Role>(individual_position).is_ok_and(|role| {
+        role != Role::None && team_position != Role::None && role != team_position
+    })
+}
+
+/// Why the player's side of a game should be left out of their stats, if it
+/// should.
+pub fn exclusion_reason(
+    state: &State,
+    m: &Match,
+    timeline: Option<&json::Timeline>,
+    puuid: &str,
+) -> Option<ExclusionReason> {
+    if state
+        .excluded_games
+        .get(&MatchAnnotation::id(&m.metadata.match_id, puuid))
+        .is_some()
+    {
+        return Some(ExclusionReason::Manual);
+    }
+    let player = get_player(m, puuid);
+    if player.game_ended_in_early_surrender {
+        return Some(ExclusionReason::Remake);
+    }
+    if let Some(timeline) = timeline {
+        let leaver = get_team(m, player).iter().any(|teammate| {
+            let id = timeline_get_player_id(timeline, &teammate.puuid);
+            let positions = timeline
+                .info
+                .frames
+                .iter()
+                // The first frame is everyone in the fountain
+                .skip(1)
+                .filter_map(|frame| frame.participant_frames.get(&id))
+                .map(|participant_frame| participant_frame.position)
+                .collect::<Vec<_>>();
+            is_afk(&positions)
+        });
+        if leaver {
+            return Some(ExclusionReason::Leaver);
+        }
+    }
+    if is_role_swap(player.team_position, &player.individual_position) {
+        return Some(ExclusionReason::RoleSwap);
+    }
+    None
+}
+
+/// Splits games into the ones to count, and how many were left out for each
+/// reason.
+pub fn split_excluded<'a>(
+    state: &State,
+    matches: &[&'a Match],
+    puuid: &str,
+) -> (Vec<&'a Match>, Vec<(ExclusionReason, usize)>) {
+    let mut kept = vec![];
+    let mut reasons = vec![];
+    for m in matches {
+        let timeline = state.timeline_per_match.get(&m.metadata.match_id);
+        match exclusion_reason(state, m, timeline.as_deref(), puuid) {
+            Some(reason) => reasons.push(reason),
+            None => kept.push(*m),
+        }
+    }
+    let excluded = reasons.into_iter().counts().into_iter().sorted().collect();
+    (kept, excluded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_afk, is_role_swap};
+    use crate::riot_api::json::{Point, Role};
+    use test_case::test_case;
+
+    fn points(coordinates: &[i32]) -> Vec<Point> {
+        coordinates.iter().map(|&x| Point { x, y: x }).collect()
+    }
+
+    #[test_case(&[1, 2, 3, 4, 5, 6], false)]
+    #[test_case(&[1, 2, 2, 2, 2, 3], false)]
+    #[test_case(&[1, 2, 2, 2, 2, 2, 2], true)]
+    #[test_case(&[], false)]
+    fn test_is_afk(coordinates: &[i32], expected: bool) {
+        assert_eq!(is_afk(&points(coordinates)), expected);
+    }
+
+    #[test_case(Role::Middle, "MIDDLE", false)]
+    #[test_case(Role::Middle, "TOP", true)]
+    #[test_case(Role::Support, "UTILITY", false)]
+    #[test_case(Role::Bottom, "Invalid", false)]
+    #[test_case(Role::None, "JUNGLE", false)]
+    fn test_is_role_swap(team_position: Role, individual_position: &str, expected: bool) {
+        assert_eq!(is_role_swap(team_position, individual_position), expected);
+    }
+}
@@ -0,0 +1,415 @@
+use std::fmt::{self, Display, Formatter};
+
+// Parsing and evaluating recurse, so these keep expressions people save from
+// running out of stack.
+const MAX_LENGTH: usize = 500;
+const MAX_DEPTH: usize = 32;
+
+/// What an expression can refer to: named numbers, and functions of numbers.
+pub trait Environment {
+    fn variable(&self, name: &str) -> Option<f64>;
+    /// Errors with `UnknownFunction` if there's no function with this name
+    /// that takes this many arguments.
+    fn call(&self, name: &str, args: &[f64]) -> Result<f64, EvaluationError>;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EvaluationError {
+    UnknownVariable(String),
+    UnknownFunction(String, usize),
+    /// Usually means the metric doesn't apply to a game, like damage per gold
+    /// in a remake, rather than a mistake in the expression.
+    DivisionByZero,
+    /// The game doesn't have the value, like the gold at 15 minutes in a
+    /// game that ended before that.
+    Unavailable,
+}
+
+impl Display for EvaluationError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            EvaluationError::UnknownVariable(name) => write!(f, "Unknown variable {name}"),
+            EvaluationError::UnknownFunction(name, args) => {
+                write!(
+                    f,
+                    "There's no function {name} that takes {args} argument(s)"
+                )
+            }
+            EvaluationError::DivisionByZero => write!(f, "Division by zero"),
+            EvaluationError::Unavailable => write!(f, "Not available in this game"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+/// A parsed arithmetic expression, like `(kills + assists) / game_minutes`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expression {
+    Number(f64),
+    Variable(String),
+    Call(String, Vec<Expression>),
+    Negate(Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Operator(Operator),
+    OpenParen,
+    CloseParen,
+    Comma,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Token::Number(number) => write!(f, "{number}"),
+            Token::Identifier(name) => write!(f, "{name}"),
+            Token::Operator(Operator::Add) => write!(f, "+"),
+            Token::Operator(Operator::Subtract) => write!(f, "-"),
+            Token::Operator(Operator::Multiply) => write!(f, "*"),
+            Token::Operator(Operator::Divide) => write!(f, "/"),
+            Token::OpenParen => write!(f, "("),
+            Token::CloseParen => write!(f, ")"),
+            Token::Comma => write!(f, ","),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c.is_ascii_digit() || c == '.' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_ascii_digit() || c == '.') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            let number = &input[start..end];
+            tokens.push(Token::Number(
+                number
+                    .parse()
+                    .map_err(|_| format!("Invalid number {number}"))?,
+            ));
+            continue;
+        }
+        if c.is_ascii_alphabetic() || c == '_' {
+            let mut end = start;
+            while let Some(&(i, c)) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                end = i + c.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token::Identifier(input[start..end].to_string()));
+            continue;
+        }
+        tokens.push(match c {
+            '+' => Token::Operator(Operator::Add),
+            '-' => Token::Operator(Operator::Subtract),
+            '*' => Token::Operator(Operator::Multiply),
+            '/' => Token::Operator(Operator::Divide),
+            '(' => Token::OpenParen,
+            ')' => Token::CloseParen,
+            ',' => Token::Comma,
+            _ => return Err(format!("Unexpected character {c:?}")),
+        });
+        chars.next();
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent, one function per precedence level.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    /// How many parentheses, calls and negations we're inside of
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), String> {
+        match self.next() {
+            Some(token) if token == *expected => Ok(()),
+            Some(token) => Err(format!("Expected {expected}, found {token}")),
+            None => Err(format!("Expected {expected}, found the end")),
+        }
+    }
+
+    fn sum(&mut self) -> Result<Expression, String> {
+        let mut left = self.product()?;
+        while let Some(Token::Operator(operator @ (Operator::Add | Operator::Subtract))) =
+            self.peek()
+        {
+            let operator = *operator;
+            self.next();
+            let right = self.product()?;
+            left = Expression::Binary(operator, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn product(&mut self) -> Result<Expression, String> {
+        let mut left = self.unary()?;
+        while let Some(Token::Operator(operator @ (Operator::Multiply | Operator::Divide))) =
+            self.peek()
+        {
+            let operator = *operator;
+            self.next();
+            let right = self.unary()?;
+            left = Expression::Binary(operator, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expression, String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(format!("Nested more than {MAX_DEPTH} levels deep"));
+        }
+        let expression = if self.peek() == Some(&Token::Operator(Operator::Subtract)) {
+            self.next();
+            self.unary()
+                .map(|inner| Expression::Negate(Box::new(inner)))
+        } else {
+            self.atom()
+        };
+        self.depth -= 1;
+        expression
+    }
+
+    fn atom(&mut self) -> Result<Expression, String> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(Expression::Number(number)),
+            Some(Token::Identifier(name)) => {
+                if self.peek() != Some(&Token::OpenParen) {
+                    return Ok(Expression::Variable(name));
+                }
+                self.next();
+                let mut args = vec![];
+                if self.peek() == Some(&Token::CloseParen) {
+                    self.next();
+                } else {
+                    loop {
+                        args.push(self.sum()?);
+                        match self.next() {
+                            Some(Token::Comma) => {}
+                            Some(Token::CloseParen) => break,
+                            Some(token) => return Err(format!("Expected , or ), found {token}")),
+                            None => return Err("Expected ), found the end".to_string()),
+                        }
+                    }
+                }
+                Ok(Expression::Call(name, args))
+            }
+            Some(Token::OpenParen) => {
+                let inner = self.sum()?;
+                self.expect(&Token::CloseParen)?;
+                Ok(inner)
+            }
+            Some(token) => Err(format!("Unexpected {token}")),
+            None => Err("Unexpected end of the expression".to_string()),
+        }
+    }
+}
+
+impl Expression {
+    pub fn parse(input: &str) -> Result<Self, String> {
+        if input.len() > MAX_LENGTH {
+            return Err(format!("Longer than {MAX_LENGTH} characters"));
+        }
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            position: 0,
+            depth: 0,
+        };
+        let expression = parser.sum()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("Unexpected {token} after the expression"));
+        }
+        Ok(expression)
+    }
+
+    /// Checks every variable and function the expression uses, including
+    /// the ones evaluating it would never get to, e.g. after a division by
+    /// zero.
+    pub fn check_names(
+        &self,
+        is_variable: &dyn Fn(&str) -> bool,
+        is_function: &dyn Fn(&str, usize) -> bool,
+    ) -> Result<(), EvaluationError> {
+        match self {
+            Expression::Number(_) => Ok(()),
+            Expression::Variable(name) if is_variable(name) => Ok(()),
+            Expression::Variable(name) => Err(EvaluationError::UnknownVariable(name.clone())),
+            Expression::Call(name, args) => {
+                if !matches!((name.as_str(), args.len()), ("min" | "max", 2))
+                    && !is_function(name, args.len())
+                {
+                    return Err(EvaluationError::UnknownFunction(name.clone(), args.len()));
+                }
+                args.iter()
+                    .try_for_each(|arg| arg.check_names(is_variable, is_function))
+            }
+            Expression::Negate(inner) => inner.check_names(is_variable, is_function),
+            Expression::Binary(_, left, right) => {
+                left.check_names(is_variable, is_function)?;
+                right.check_names(is_variable, is_function)
+            }
+        }
+    }
+
+    pub fn evaluate(&self, environment: &dyn Environment) -> Result<f64, EvaluationError> {
+        match self {
+            Expression::Number(number) => Ok(*number),
+            Expression::Variable(name) => environment
+                .variable(name)
+                .ok_or_else(|| EvaluationError::UnknownVariable(name.clone())),
+            Expression::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.evaluate(environment))
+                    .collect::<Result<Vec<_>, _>>()?;
+                match (name.as_str(), args.as_slice()) {
+                    ("min", [a, b]) => Ok(a.min(*b)),
+                    ("max", [a, b]) => Ok(a.max(*b)),
+                    _ => environment.call(name, &args),
+                }
+            }
+            Expression::Negate(inner) => Ok(-inner.evaluate(environment)?),
+            Expression::Binary(operator, left, right) => {
+                let left = left.evaluate(environment)?;
+                let right = right.evaluate(environment)?;
+                match operator {
+                    Operator::Add => Ok(left + right),
+                    Operator::Subtract => Ok(left - right),
+                    Operator::Multiply => Ok(left * right),
+                    Operator::Divide if right == 0.0 => Err(EvaluationError::DivisionByZero),
+                    Operator::Divide => Ok(left / right),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Environment, EvaluationError, Expression};
+    use test_case::test_case;
+
+    struct TestEnvironment;
+
+    impl Environment for TestEnvironment {
+        fn variable(&self, name: &str) -> Option<f64> {
+            match name {
+                "kills" => Some(6.0),
+                "deaths" => Some(3.0),
+                "assists" => Some(9.0),
+                "zero" => Some(0.0),
+                _ => None,
+            }
+        }
+        fn call(&self, name: &str, args: &[f64]) -> Result<f64, EvaluationError> {
+            match (name, args) {
+                ("double", [a]) => Ok(a * 2.0),
+                _ => Err(EvaluationError::UnknownFunction(
+                    name.to_string(),
+                    args.len(),
+                )),
+            }
+        }
+    }
+
+    #[test_case("1 + 2 * 3", 7.0)]
+    #[test_case("(1 + 2) * 3", 9.0)]
+    #[test_case("10 - 4 - 3", 3.0)]
+    #[test_case("(kills + assists) / deaths", 5.0)]
+    #[test_case("-kills + 1", -5.0)]
+    #[test_case("double(kills) / 4", 3.0)]
+    #[test_case("max(kills, assists) - min(1.5, deaths)", 7.5)]
+    fn test_evaluate(input: &str, expected: f64) {
+        let value = Expression::parse(input)
+            .unwrap()
+            .evaluate(&TestEnvironment)
+            .unwrap();
+        assert!((value - expected).abs() < 1e-9);
+    }
+
+    #[test_case("kills +" ; "missing operand")]
+    #[test_case("(kills" ; "unclosed parenthesis")]
+    #[test_case("kills deaths" ; "missing operator")]
+    #[test_case("kills % 2" ; "unknown operator")]
+    #[test_case("double(kills," ; "unclosed call")]
+    fn test_parse_error(input: &str) {
+        assert!(Expression::parse(input).is_err());
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        let nested = |depth| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Expression::parse(&nested(31)).is_ok());
+        assert_eq!(
+            Expression::parse(&nested(32)),
+            Err("Nested more than 32 levels deep".to_string())
+        );
+        assert!(Expression::parse(&"-".repeat(100_000)).is_err());
+        assert!(Expression::parse(&format!("{}1", "double(".repeat(40))).is_err());
+        let long = vec!["kills"; 100].join(" + ");
+        assert_eq!(
+            Expression::parse(&long),
+            Err("Longer than 500 characters".to_string())
+        );
+    }
+
+    #[test_case("kills / zero", EvaluationError::DivisionByZero)]
+    #[test_case("kills / (deaths - 3)", EvaluationError::DivisionByZero)]
+    #[test_case("gold_earned", EvaluationError::UnknownVariable("gold_earned".to_string()))]
+    #[test_case("double(kills, 2)", EvaluationError::UnknownFunction("double".to_string(), 2))]
+    fn test_evaluate_error(input: &str, expected: EvaluationError) {
+        let expression = Expression::parse(input).unwrap();
+        assert_eq!(expression.evaluate(&TestEnvironment), Err(expected));
+    }
+
+    #[test_case("max(kills, 2) / -double(deaths)", None ; "known")]
+    #[test_case("1 / 0 + gold_earned", Some(EvaluationError::UnknownVariable("gold_earned".to_string())) ; "after division by zero")]
+    #[test_case("kills * triple(2)", Some(EvaluationError::UnknownFunction("triple".to_string(), 1)) ; "unknown function")]
+    #[test_case("min(kills)", Some(EvaluationError::UnknownFunction("min".to_string(), 1)) ; "wrong argument count")]
+    #[test_case("double(nope)", Some(EvaluationError::UnknownVariable("nope".to_string())) ; "in arguments")]
+    fn test_check_names(input: &str, expected: Option<EvaluationError>) {
+        let expression = Expression::parse(input).unwrap();
+        let is_variable = |name: &str| TestEnvironment.variable(name).is_some();
+        let is_function = |name: &str, args: usize| name == "double" && args == 1;
+        assert_eq!(
+            expression.check_names(&is_variable, &is_function),
+            expected.map_or(Ok(()), Err)
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use crate::riot_api::json;
+use std::collections::{HashMap, HashSet};
+
+// Kills that are at most this far apart in time (in milliseconds) and space
+// are considered part of the same fight.
+const FIGHT_GAP_MS: i64 = 15_000;
+const FIGHT_RADIUS: f64 = 3000.0;
+// A single kill is a pick, not a fight.
+const MIN_FIGHT_KILLS: usize = 2;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FightInvolvement {
+    Participated,
+    DiedFirst,
+    Absent,
+}
+
+#[derive(Debug)]
+pub struct Fight<'a> {
+    pub kills: Vec<&'a json::ChampionKill>,
+}
+
+impl Fight<'_> {
+    fn last_timestamp_ms(&self) -> i64 {
+        // Fights are never empty, they're created with the first kill
+        self.kills.last().unwrap().timestamp.num_milliseconds()
+    }
+
+    fn is_near(&self, kill: &json::ChampionKill) -> bool {
+        kill.timestamp.num_milliseconds() - self.last_timestamp_ms() <= FIGHT_GAP_MS
+            && self
+                .kills
+                .iter()
+                .any(|k| k.position.distance(kill.position) <= FIGHT_RADIUS)
+    }
+
+    fn participants(&self) -> HashSet<usize> {
+        self.kills
+            .iter()
+            .flat_map(|k| {
+                [k.killer_id, k.victim_id]
+                    .into_iter()
+                    .chain(k.assisting_participant_ids.iter().copied())
+            })
+            .collect()
+    }
+
+    pub fn involvement(&self, participant_id: usize) -> FightInvolvement {
+        if self.kills[0].victim_id == participant_id {
+            FightInvolvement::DiedFirst
+        } else if self.participants().contains(&participant_id) {
+            FightInvolvement::Participated
+        } else {
+            FightInvolvement::Absent
+        }
+    }
+
+    /// The team that lost fewer champions, or `None` for an even trade.
+    pub fn winner(&self, team_per_participant: &HashMap<usize, i32>) -> Option<i32> {
+        let deaths_per_team = self
+            .kills
+            .iter()
+            .filter_map(|k| team_per_participant.get(&k.victim_id))
+            .fold(HashMap::<i32, usize>::new(), |mut counts, team| {
+                *counts.entry(*team).or_default() += 1;
+                counts
+            });
+        let mut teams = team_per_participant.values().copied().collect::<Vec<_>>();
+        teams.sort_unstable();
+        teams.dedup();
+        let [blue, red] = teams[..] else {
+            return None;
+        };
+        let blue_deaths = deaths_per_team.get(&blue).copied().unwrap_or(0);
+        let red_deaths = deaths_per_team.get(&red).copied().unwrap_or(0);
+        match blue_deaths.cmp(&red_deaths) {
+            std::cmp::Ordering::Less => Some(blue),
+            std::cmp::Ordering::Greater => Some(red),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
+
+/// Cluster all champion kills in a game into fights, by proximity in time and
+/// space. Kills that don't end up with at least one other kill are dropped.
+pub fn find_fights(frames: &[json::Frame]) -> Vec<Fight<'_>> {
+    let mut fights: Vec<Fight> = vec![];
+    let kills = frames.iter().flat_map(|f| {
+        f.events.iter().filter_map(|e| {
+            if let json::Event::ChampionKill(kill) = e {
+                Some(kill)
+            } else {
+                None
+            }
+        })
+    });
+    for kill in kills {
+        // Only the most recent fights can still be going on, and those are at
+        // the end.
+        let open_fight = fights.iter_mut().rev().find(|fight| fight.is_near(kill));
+        match open_fight {
+            Some(fight) => fight.kills.push(kill),
+            None => fights.push(Fight { kills: vec![kill] }),
+        }
+    }
+    fights
+        .into_iter()
+        .filter(|fight| fight.kills.len() >= MIN_FIGHT_KILLS)
+        .collect()
+}
+
+#[derive(Default)]
+pub struct FightCounts {
+    pub fights: u32,
+    pub participated: u32,
+    pub died_first: u32,
+    pub won: u32,
+}
+
+pub fn count_fights(
+    fights: &[Fight],
+    participant_id: usize,
+    team_per_participant: &HashMap<usize, i32>,
+) -> FightCounts {
+    let team = team_per_participant.get(&participant_id).copied();
+    fights
+        .iter()
+        .fold(FightCounts::default(), |mut counts, fight| {
+            counts.fights += 1;
+            match fight.involvement(participant_id) {
+                FightInvolvement::Absent => return counts,
+                FightInvolvement::DiedFirst => counts.died_first += 1,
+                FightInvolvement::Participated => {}
+            }
+            counts.participated += 1;
+            if team.is_some() && fight.winner(team_per_participant) == team {
+                counts.won += 1;
+            }
+            counts
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{count_fights, find_fights, FightInvolvement};
+    use crate::riot_api::json;
+    use chrono::TimeDelta;
+    use std::collections::HashMap;
+
+    fn kill(seconds: i64, killer_id: usize, victim_id: usize, x: i32, y: i32) -> json::Event {
+        json::Event::ChampionKill(json::ChampionKill {
+            assisting_participant_ids: vec![],
+            bounty: 300,
+            kill_streak_length: 0,
+            killer_id,
+            position: json::Point { x, y },
+            shutdown_bounty: 0,
+            timestamp: TimeDelta::seconds(seconds),
+            victim_damage_dealt: vec![],
+            victim_damage_received: vec![],
+            victim_id,
+        })
+    }
+
+    fn frames(events: Vec<json::Event>) -> Vec<json::Frame> {
+        vec![json::Frame {
+            events,
+            participant_frames: HashMap::new(),
+            timestamp: TimeDelta::minutes(1),
+        }]
+    }
+
+    fn teams() -> HashMap<usize, i32> {
+        (1..=10)
+            .map(|id| (id, if id <= 5 { 100 } else { 200 }))
+            .collect()
+    }
+
+    #[test]
+    fn test_clusters_by_time_and_distance() {
+        let frames = frames(vec![
+            // A fight mid lane
+            kill(600, 1, 6, 7000, 7000),
+            kill(605, 7, 2, 7500, 7200),
+            kill(612, 1, 7, 7200, 6800),
+            // A pick on the other side of the map at the same time
+            kill(606, 9, 4, 1000, 13000),
+            // Same place, but much later
+            kill(700, 3, 8, 7000, 7000),
+            kill(710, 8, 3, 7100, 7000),
+        ]);
+        let fights = find_fights(&frames);
+        assert_eq!(fights.len(), 2);
+        assert_eq!(fights[0].kills.len(), 3);
+        assert_eq!(fights[1].kills.len(), 2);
+    }
+
+    #[test]
+    fn test_involvement_and_winner() {
+        let frames = frames(vec![
+            kill(600, 1, 6, 7000, 7000),
+            kill(605, 7, 2, 7500, 7200),
+            kill(612, 1, 7, 7200, 6800),
+        ]);
+        let fights = find_fights(&frames);
+        let fight = &fights[0];
+        assert_eq!(fight.involvement(6), FightInvolvement::DiedFirst);
+        assert_eq!(fight.involvement(1), FightInvolvement::Participated);
+        assert_eq!(fight.involvement(2), FightInvolvement::Participated);
+        assert_eq!(fight.involvement(3), FightInvolvement::Absent);
+        assert_eq!(fight.winner(&teams()), Some(100));
+
+        let counts = count_fights(&fights, 6, &teams());
+        assert_eq!(counts.fights, 1);
+        assert_eq!(counts.participated, 1);
+        assert_eq!(counts.died_first, 1);
+        assert_eq!(counts.won, 0);
+    }
+
+    #[test]
+    fn test_even_trade_has_no_winner() {
+        let frames = frames(vec![
+            kill(600, 1, 6, 7000, 7000),
+            kill(601, 6, 1, 7000, 7000),
+        ]);
+        let fights = find_fights(&frames);
+        assert_eq!(fights[0].winner(&teams()), None);
+    }
+}
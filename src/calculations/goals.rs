@@ -0,0 +1,158 @@
+use super::{
+    custom_metric,
+    exclusions::split_excluded,
+    metric::{self, Metric},
+    metrics::{self, GameMetrics},
+    percentage,
+};
+use crate::{
+    goal::{Comparison, Goal},
+    normalize_champion_name,
+    riot_api::json::Match,
+    Player, State, CHAMP_NAMES,
+};
+use log::warn;
+use std::fmt::Write;
+
+/// How a player is doing on a goal, in one group of games.
+#[derive(Clone, Debug)]
+pub struct GoalProgress {
+    pub id: String,
+    pub description: String,
+    /// Games that the goal applies to
+    pub games: u32,
+    /// Games where the goal was hit
+    pub hits: u32,
+    pub hit_rate: f64,
+    /// The metric over all those games together
+    pub value: String,
+    pub met: bool,
+}
+
+/// Counts the values that hit the goal, and how many values there are.
+fn tally(comparison: Comparison, threshold: f64, values: &[f64]) -> (u32, u32) {
+    let hits = values
+        .iter()
+        .filter(|&&value| comparison.holds(value, threshold))
+        .count();
+    (
+        u32::try_from(hits).unwrap(),
+        u32::try_from(values.len()).unwrap(),
+    )
+}
+
+fn applies_to(goal: &Goal, game: &GameMetrics) -> bool {
+    goal.role.is_none_or(|role| role == game.role)
+        && goal
+            .champion
+            .as_ref()
+            .is_none_or(|champion| *champion == normalize_champion_name(&game.champion))
+        && game.date.date_naive() >= goal.start
+}
+
+fn progress(id: &str, goal: &Goal, metric: &dyn Metric, games: &[GameMetrics]) -> GoalProgress {
+    let games = games
+        .iter()
+        .filter(|game| applies_to(goal, game))
+        .cloned()
+        .collect::<Vec<_>>();
+    let values = games
+        .iter()
+        .filter_map(|game| metric.extract(game))
+        .collect::<Vec<_>>();
+    let (hits, total) = tally(goal.comparison, goal.threshold, &values);
+    let value = metric.aggregate(&games).number;
+    let mut description = format!(
+        "{} {} {}",
+        metric.name(),
+        goal.comparison,
+        metric.format(goal.threshold)
+    );
+    if let Some(champion) = &goal.champion {
+        let champion = CHAMP_NAMES
+            .get(champion.as_str())
+            .copied()
+            .unwrap_or(champion.as_str());
+        write!(description, " on {champion}").unwrap();
+    }
+    if let Some(role) = goal.role {
+        write!(description, " in {}", role.lowercase()).unwrap();
+    }
+    write!(description, " since {}", goal.start).unwrap();
+    GoalProgress {
+        id: id.to_string(),
+        description,
+        games: total,
+        hits,
+        hit_rate: percentage(hits, total),
+        value: if value.is_nan() {
+            "-".to_string()
+        } else {
+            metric.format(value)
+        },
+        met: !value.is_nan() && goal.comparison.holds(value, goal.threshold),
+    }
+}
+
+/// Progress on every goal of `player` over `matches`.
+pub fn goal_progress(
+    state: &State,
+    player: &Player,
+    matches: &[&Match],
+    puuid: &str,
+) -> Vec<GoalProgress> {
+    let player = player.clone().normalized();
+    let goals = state
+        .goals
+        .list()
+        .into_iter()
+        .filter(|(_, goal)| goal.player == player)
+        .collect::<Vec<_>>();
+    if goals.is_empty() {
+        return vec![];
+    }
+    let (matches, _) = split_excluded(state, matches, puuid);
+    let custom_metrics = custom_metric::load(state);
+    let games = matches
+        .iter()
+        .map(|m| {
+            let timeline = state.timeline_per_match.get(&m.metadata.match_id).unwrap();
+            let mut game = metrics::game_metrics(m, &timeline, puuid);
+            game.custom = custom_metric::evaluate_all(&custom_metrics, m, &timeline, puuid);
+            game
+        })
+        .collect::<Vec<_>>();
+    goals
+        .iter()
+        .filter_map(|(id, goal)| {
+            let Some(metric) = metric::find(&goal.metric, &custom_metrics) else {
+                // The custom metric it was about is gone
+                warn!("Goal {id} has unknown metric {}", goal.metric);
+                return None;
+            };
+            Some(progress(id, goal, metric, &games))
+        })
+        .collect()
+}
+
+/// Every metric a goal can be about, as ID and name.
+pub fn goal_metrics(state: &State) -> Vec<(String, String)> {
+    let custom_metrics = custom_metric::load(state);
+    metric::all_metrics(&custom_metrics)
+        .map(|metric| (metric.id().to_string(), metric.name().to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tally;
+    use crate::goal::Comparison;
+    use test_case::test_case;
+
+    #[test_case(Comparison::AtLeast, 7.0, &[6.5, 7.0, 8.2, 5.0], (2, 4))]
+    #[test_case(Comparison::AtMost, 5.0, &[3.0, 5.0, 6.0], (2, 3))]
+    #[test_case(Comparison::AtMost, 5.0, &[], (0, 0))]
+    fn test_tally(comparison: Comparison, threshold: f64, values: &[f64], expected: (u32, u32)) {
+        assert_eq!(tally(comparison, threshold, values), expected);
+    }
+}
@@ -0,0 +1,100 @@
+use super::{
+    cs_per_minute, frame_stats_at, get_opponent, get_player, match_detail::format_time,
+    timeline_get_player_id, GameSummary,
+};
+use crate::{annotation::MatchAnnotation, normalize_champion_name, riot_api::json::Match, State};
+use chrono::TimeDelta;
+use itertools::Itertools;
+use serde::Deserialize;
+
+// Laning is mostly over by the time plates fall.
+const LANE_END_MINUTE: i64 = 14;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum GameResult {
+    Win,
+    Loss,
+}
+
+/// Filters on top of role and champion, which are part of the path.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct HistoryFilter {
+    pub enemy: Option<String>,
+    pub result: Option<GameResult>,
+}
+
+#[derive(Clone, Debug)]
+pub struct HistoryRow {
+    pub game: GameSummary,
+    /// `None` if we aren't sure who the lane opponent was
+    pub opponent: Option<String>,
+    pub cs_per_minute: f64,
+    pub gold_diff_at_lane_end: Option<i32>,
+    pub duration: TimeDelta,
+    pub patch: String,
+    pub annotation: MatchAnnotation,
+}
+
+impl HistoryRow {
+    pub fn duration_display(&self) -> String {
+        format_time(self.duration)
+    }
+}
+
+impl HistoryFilter {
+    fn matches(&self, state: &State, m: &Match, puuid: &str) -> bool {
+        let player = get_player(m, puuid);
+        let result_match = self
+            .result
+            .is_none_or(|result| player.win == (result == GameResult::Win));
+        let enemy_match = self.enemy.as_deref().is_none_or(|enemy| {
+            let timeline = state.timeline_per_match.get(&m.metadata.match_id);
+            get_opponent(m, timeline.as_deref(), player).is_some_and(|opponent| {
+                normalize_champion_name(&opponent.champion_name) == normalize_champion_name(enemy)
+            })
+        });
+        result_match && enemy_match
+    }
+}
+
+pub fn history_rows(
+    state: &State,
+    matches: &[&Match],
+    puuid: &str,
+    filter: &HistoryFilter,
+) -> Vec<HistoryRow> {
+    matches
+        .iter()
+        .filter(|m| filter.matches(state, m, puuid))
+        .map(|m| {
+            let player = get_player(m, puuid);
+            let timeline = state.timeline_per_match.get(&m.metadata.match_id);
+            let opponent = get_opponent(m, timeline.as_deref(), player);
+            let gold_diff_at_lane_end = timeline
+                .and_then(|timeline| {
+                    frame_stats_at(
+                        &timeline.info.frames,
+                        timeline_get_player_id(&timeline, puuid),
+                        opponent.map(|opponent| timeline_get_player_id(&timeline, &opponent.puuid)),
+                        TimeDelta::minutes(LANE_END_MINUTE),
+                    )
+                })
+                .and_then(|stats_at| stats_at.gold_diff);
+            HistoryRow {
+                game: GameSummary::new(m, puuid),
+                opponent: opponent.map(|opponent| opponent.champion_name.clone()),
+                cs_per_minute: cs_per_minute(m, player),
+                gold_diff_at_lane_end,
+                duration: m.info.game_duration,
+                patch: m.info.patch(),
+                annotation: state
+                    .annotations
+                    .get(&MatchAnnotation::id(&m.metadata.match_id, puuid))
+                    .unwrap_or_default(),
+            }
+        })
+        // Newest first
+        .sorted_by_key(|row| std::cmp::Reverse(row.game.start))
+        .collect()
+}
@@ -0,0 +1,295 @@
+use super::{get_player, infer_opponent, timeline_get_player_id, OpponentSource};
+use crate::{
+    riot_api::json::{self, Role},
+    ITEM_NAMES,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::HashMap;
+
+// Positions are in map units, the minimap image is 512 pixels wide.
+const MAP_UNITS_PER_PIXEL: i32 = 29;
+const MINIMAP_SIZE: i32 = 512;
+
+#[derive(Clone, Debug)]
+pub struct CurvePoint {
+    pub minute: i64,
+    pub gold: i32,
+    pub xp: i32,
+    pub cs: i32,
+    pub opponent_gold: Option<i32>,
+    pub opponent_xp: Option<i32>,
+    pub opponent_cs: Option<i32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct EventRow {
+    pub time: String,
+    pub kind: &'static str,
+    pub description: String,
+    pub involves_player: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct MatchDetail {
+    pub match_id: String,
+    pub start: DateTime<Utc>,
+    pub duration: String,
+    pub patch: String,
+    pub champion: String,
+    pub role: Role,
+    pub opponent_champion: Option<String>,
+    pub opponent_source: OpponentSource,
+    /// From 0 to 1
+    pub opponent_confidence: f64,
+    pub win: bool,
+    pub kills: i32,
+    pub deaths: i32,
+    pub assists: i32,
+    pub curves: Vec<CurvePoint>,
+    pub events: Vec<EventRow>,
+    /// Position at every frame, in minimap pixels
+    pub path: Vec<json::Point>,
+}
+
+impl MatchDetail {
+    /// The path as an SVG polyline `points` attribute.
+    pub fn svg_path(&self) -> String {
+        self.path
+            .iter()
+            .map(|point| format!("{},{}", point.x, point.y))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    pub fn opponent_confidence_percent(&self) -> String {
+        format!("{:.0}", self.opponent_confidence * 100.0)
+    }
+}
+
+pub(super) fn format_time(time: TimeDelta) -> String {
+    format!("{}:{:02}", time.num_minutes(), time.num_seconds() % 60)
+}
+
+fn cs(frame: &json::ParticipantFrame) -> i32 {
+    frame.minions_killed + frame.jungle_minions_killed
+}
+
+fn to_minimap(position: json::Point) -> json::Point {
+    json::Point {
+        x: position.x / MAP_UNITS_PER_PIXEL,
+        y: MINIMAP_SIZE - position.y / MAP_UNITS_PER_PIXEL,
+    }
+}
+
+fn item_name(item_id: i32) -> String {
+    ITEM_NAMES
+        .get(&item_id)
+        .map_or_else(|| format!("item {item_id}"), ToString::to_string)
+}
+
+/// The player's and their opponent's gold, XP and CS at every frame.
+fn curves(
+    timeline: &json::Timeline,
+    player_id: usize,
+    opponent_id: Option<usize>,
+) -> Vec<CurvePoint> {
+    timeline
+        .info
+        .frames
+        .iter()
+        .filter_map(|frame| {
+            let player_frame = frame.participant_frames.get(&player_id)?;
+            let opponent_frame = opponent_id.and_then(|id| frame.participant_frames.get(&id));
+            Some(CurvePoint {
+                minute: frame.timestamp.num_minutes(),
+                gold: player_frame.total_gold,
+                xp: player_frame.xp,
+                cs: cs(player_frame),
+                opponent_gold: opponent_frame.map(|f| f.total_gold),
+                opponent_xp: opponent_frame.map(|f| f.xp),
+                opponent_cs: opponent_frame.map(cs),
+            })
+        })
+        .collect()
+}
+
+/// Where the player was at every frame, in minimap pixels.
+fn movement(timeline: &json::Timeline, player_id: usize) -> Vec<json::Point> {
+    timeline
+        .info
+        .frames
+        .iter()
+        .filter_map(|frame| frame.participant_frames.get(&player_id))
+        .map(|f| to_minimap(f.position))
+        .collect()
+}
+
+fn team_name(team_id: i32) -> &'static str {
+    if team_id == 100 {
+        "Blue"
+    } else {
+        "Red"
+    }
+}
+
+/// A row for the event list, or `None` for events that aren't shown. `name`
+/// turns timeline participant IDs into champion names.
+fn event_row(
+    event: &json::Event,
+    player_id: usize,
+    name: impl Fn(usize) -> String,
+) -> Option<EventRow> {
+    let (timestamp, kind, description, involves_player) = match event {
+        json::Event::ChampionKill(kill) => (
+            kill.timestamp,
+            "Kill",
+            format!("{} killed {}", name(kill.killer_id), name(kill.victim_id)),
+            kill.killer_id == player_id
+                || kill.victim_id == player_id
+                || kill.assisting_participant_ids.contains(&player_id),
+        ),
+        json::Event::BuildingKill {
+            building_type,
+            lane_type,
+            team_id,
+            timestamp,
+            tower_type,
+            killer_id,
+            assisting_participant_ids,
+            ..
+        } => (
+            *timestamp,
+            "Objective",
+            format!(
+                "{} {} {} destroyed",
+                team_name(*team_id),
+                lane_type.to_lowercase().replace('_', " "),
+                tower_type
+                    .as_deref()
+                    .unwrap_or(building_type)
+                    .to_lowercase()
+                    .replace('_', " "),
+            ),
+            *killer_id == player_id || assisting_participant_ids.contains(&player_id),
+        ),
+        json::Event::EliteMonsterKill {
+            killer_id,
+            killer_team_id,
+            monster_sub_type,
+            monster_type,
+            timestamp,
+            assisting_participant_ids,
+            ..
+        } => (
+            *timestamp,
+            "Objective",
+            format!(
+                "{} ({}) killed {}",
+                name(*killer_id),
+                team_name(*killer_team_id),
+                monster_sub_type
+                    .as_deref()
+                    .unwrap_or(monster_type)
+                    .to_lowercase()
+                    .replace('_', " "),
+            ),
+            *killer_id == player_id || assisting_participant_ids.contains(&player_id),
+        ),
+        // Item buys and level ups of everyone else would drown out the rest
+        json::Event::ItemPurchased {
+            item_id,
+            participant_id,
+            timestamp,
+        } if *participant_id == player_id => (
+            *timestamp,
+            "Item",
+            format!("Bought {}", item_name(*item_id)),
+            true,
+        ),
+        json::Event::LevelUp {
+            level,
+            participant_id,
+            timestamp,
+        } if *participant_id == player_id => {
+            (*timestamp, "Level", format!("Reached level {level}"), true)
+        }
+        _ => return None,
+    };
+    Some(EventRow {
+        time: format_time(timestamp),
+        kind,
+        description,
+        involves_player,
+    })
+}
+
+/// Kills and objectives, and the player's item buys and level ups.
+fn events(m: &json::Match, timeline: &json::Timeline, player_id: usize) -> Vec<EventRow> {
+    // Timeline participant IDs to champion names, for the event descriptions
+    let names = timeline
+        .info
+        .participants
+        .iter()
+        .filter_map(|tp| {
+            let p = m.info.participants.iter().find(|p| p.puuid == tp.puuid)?;
+            Some((tp.participant_id, p.champion_name.clone()))
+        })
+        .collect::<HashMap<_, _>>();
+    let name = |id: usize| {
+        names
+            .get(&id)
+            .cloned()
+            // Killer ID 0 is minions, turrets and such
+            .unwrap_or_else(|| "Minions/turret".to_string())
+    };
+    timeline
+        .info
+        .frames
+        .iter()
+        .flat_map(|frame| &frame.events)
+        .filter_map(|event| event_row(event, player_id, name))
+        .collect()
+}
+
+pub fn match_detail(m: &json::Match, timeline: &json::Timeline, puuid: &str) -> MatchDetail {
+    let player = get_player(m, puuid);
+    let inferred = infer_opponent(m, Some(timeline), player);
+    // Better no opponent than one picked at random
+    let opponent = (inferred.source != OpponentSource::Guess).then_some(inferred.participant);
+    let player_id = timeline_get_player_id(timeline, puuid);
+    let opponent_id = opponent.map(|o| timeline_get_player_id(timeline, &o.puuid));
+
+    MatchDetail {
+        match_id: m.metadata.match_id.clone(),
+        start: m.info.game_start_timestamp,
+        duration: format_time(m.info.game_duration),
+        patch: m.info.patch(),
+        champion: player.champion_name.clone(),
+        role: player.team_position,
+        opponent_champion: opponent.map(|o| o.champion_name.clone()),
+        opponent_source: inferred.source,
+        opponent_confidence: inferred.confidence,
+        win: player.win,
+        kills: player.kills,
+        deaths: player.deaths,
+        assists: player.assists,
+        curves: curves(timeline, player_id, opponent_id),
+        events: events(m, timeline, player_id),
+        path: movement(timeline, player_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::format_time;
+    use chrono::TimeDelta;
+    use test_case::test_case;
+
+    #[test_case(0, "0:00")]
+    #[test_case(59, "0:59")]
+    #[test_case(61, "1:01")]
+    #[test_case(1834, "30:34")]
+    fn test_format_time(seconds: i64, expected: &str) {
+        assert_eq!(format_time(TimeDelta::seconds(seconds)), expected);
+    }
+}
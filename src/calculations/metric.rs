@@ -0,0 +1,462 @@
+use super::{
+    baseline::ordinal, custom_metric::CustomMetric, distribution::Summary, metrics::GameMetrics,
+    percentage, rate_samples, NumberWithOptionalDelta,
+};
+use crate::riot_api::json::Role;
+use serde::Serialize;
+use std::fmt::Debug;
+
+/// A headline stat. Everything in `METRICS`, and every custom metric, is
+/// gathered, compared, exported and shown on the stats and compare pages
+/// without any further wiring.
+pub trait Metric: Debug + Sync {
+    /// Stable identifier, used as the key in JSON output
+    fn id(&self) -> &str;
+    fn name(&self) -> &str;
+    /// The value in a single game, if the game has one
+    fn extract(&self, game: &GameMetrics) -> Option<f64>;
+    /// Sums up the values over all games.
+    fn aggregate(&self, games: &[GameMetrics]) -> NumberWithOptionalDelta;
+    fn up_is_good(&self) -> bool {
+        true
+    }
+    /// Roles the metric is shown for, all of them if `None`
+    fn roles(&self) -> Option<&'static [Role]> {
+        None
+    }
+    fn format(&self, value: f64) -> String {
+        format_number(value, false)
+    }
+    /// Whether a single game's value is on the same scale as the sum over
+    /// many games, so that a player can be ranked among other players'
+    /// games.
+    fn comparable_per_game(&self) -> bool {
+        true
+    }
+}
+
+pub fn format_number(value: f64, percent: bool) -> String {
+    if percent {
+        format!("{value:.1}%")
+    } else {
+        format!("{value:.1}")
+    }
+}
+
+/// The mean or median of the metric's value in each game that has one.
+pub fn summarize(
+    metric: &dyn Metric,
+    games: &[GameMetrics],
+    summary: Summary,
+) -> NumberWithOptionalDelta {
+    let number = NumberWithOptionalDelta::summarized(
+        games.iter().filter_map(|game| metric.extract(game)),
+        summary,
+    );
+    if metric.up_is_good() {
+        number
+    } else {
+        number.up_is_bad()
+    }
+}
+
+/// A metric that every game has a number for, summed up as the mean or
+/// median of those.
+#[derive(Debug)]
+pub struct PerGame {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub summary: Summary,
+    pub up_is_good: bool,
+    pub percent: bool,
+    pub roles: Option<&'static [Role]>,
+    pub extract: fn(&GameMetrics) -> Option<f64>,
+}
+
+impl Metric for PerGame {
+    fn id(&self) -> &str {
+        self.id
+    }
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn extract(&self, game: &GameMetrics) -> Option<f64> {
+        (self.extract)(game)
+    }
+    fn aggregate(&self, games: &[GameMetrics]) -> NumberWithOptionalDelta {
+        summarize(self, games, self.summary)
+    }
+    fn up_is_good(&self) -> bool {
+        self.up_is_good
+    }
+    fn roles(&self) -> Option<&'static [Role]> {
+        self.roles
+    }
+    fn format(&self, value: f64) -> String {
+        format_number(value, self.percent)
+    }
+}
+
+/// A percentage of one count out of another over all games together, like
+/// fights won out of fights taken part in. Averaging the per game
+/// percentages instead would make a game with one fight count as much as
+/// one with ten.
+#[derive(Debug)]
+pub struct Rate {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub up_is_good: bool,
+    /// The part and the total in a single game
+    pub counts: fn(&GameMetrics) -> (u32, u32),
+}
+
+impl Metric for Rate {
+    fn id(&self) -> &str {
+        self.id
+    }
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn extract(&self, game: &GameMetrics) -> Option<f64> {
+        let (part, total) = (self.counts)(game);
+        (total > 0).then(|| percentage(part, total))
+    }
+    fn aggregate(&self, games: &[GameMetrics]) -> NumberWithOptionalDelta {
+        let (part, total) = games
+            .iter()
+            .map(self.counts)
+            .fold((0, 0), |(part, total), (game_part, game_total)| {
+                (part + game_part, total + game_total)
+            });
+        let number = NumberWithOptionalDelta::from(percentage(part, total))
+            .with_samples(rate_samples(part, total));
+        if self.up_is_good() {
+            number
+        } else {
+            number.up_is_bad()
+        }
+    }
+    fn up_is_good(&self) -> bool {
+        self.up_is_good
+    }
+    fn format(&self, value: f64) -> String {
+        format_number(value, true)
+    }
+    // In a single game it's mostly 0% or 100%, which says nothing about where
+    // e.g. a 55% win rate falls
+    fn comparable_per_game(&self) -> bool {
+        false
+    }
+}
+
+// IDs of the built-in metrics that tables pick out by name
+pub const WINRATE: &str = "winrate";
+pub const KILLS: &str = "kills";
+pub const DEATHS: &str = "deaths";
+pub const ASSISTS: &str = "assists";
+pub const KDA: &str = "kda";
+pub const CS_PER_MINUTE: &str = "cs_per_minute";
+pub const GOLD_SHARE: &str = "gold_share";
+pub const KILL_PARTICIPATION: &str = "kill_participation";
+pub const VISION_SCORE_PER_MINUTE: &str = "vision_score_per_minute";
+
+const LANING_ROLES: &[Role] = &[Role::Top, Role::Jungle, Role::Middle, Role::Bottom];
+
+/// Every headline metric, in the order they're shown.
+pub static METRICS: &[&dyn Metric] = &[
+    &Rate {
+        id: WINRATE,
+        name: "Win rate",
+        up_is_good: true,
+        counts: |game| (u32::from(game.win), 1),
+    },
+    &PerGame {
+        id: KILLS,
+        name: "Kills",
+        summary: Summary::Mean,
+        up_is_good: true,
+        percent: false,
+        roles: None,
+        extract: |game| Some(f64::from(game.kills)),
+    },
+    &PerGame {
+        id: DEATHS,
+        name: "Deaths",
+        summary: Summary::Mean,
+        up_is_good: false,
+        percent: false,
+        roles: None,
+        extract: |game| Some(f64::from(game.deaths)),
+    },
+    &PerGame {
+        id: ASSISTS,
+        name: "Assists",
+        summary: Summary::Mean,
+        up_is_good: true,
+        percent: false,
+        roles: None,
+        extract: |game| Some(f64::from(game.assists)),
+    },
+    &PerGame {
+        id: KDA,
+        name: "KDA",
+        summary: Summary::Mean,
+        up_is_good: true,
+        percent: false,
+        roles: None,
+        extract: |game| Some(game.kda),
+    },
+    &PerGame {
+        id: CS_PER_MINUTE,
+        name: "CS/min",
+        summary: Summary::Median,
+        up_is_good: true,
+        percent: false,
+        roles: Some(LANING_ROLES),
+        extract: |game| Some(game.cs_per_minute),
+    },
+    &PerGame {
+        id: GOLD_SHARE,
+        name: "Gold share",
+        summary: Summary::Median,
+        up_is_good: true,
+        percent: true,
+        roles: None,
+        extract: |game| Some(game.gold_share),
+    },
+    &PerGame {
+        id: "champion_damage_share",
+        name: "Champion damage share",
+        summary: Summary::Median,
+        up_is_good: true,
+        percent: true,
+        roles: None,
+        extract: |game| Some(game.champion_damage_share),
+    },
+    &PerGame {
+        id: "objective_damage_share",
+        name: "Objective damage share",
+        summary: Summary::Median,
+        up_is_good: true,
+        percent: true,
+        roles: None,
+        extract: |game| Some(game.objective_damage_share),
+    },
+    &PerGame {
+        id: "vision_share",
+        name: "Vision share",
+        summary: Summary::Median,
+        up_is_good: true,
+        percent: true,
+        roles: None,
+        extract: |game| Some(game.vision_share),
+    },
+    &PerGame {
+        id: VISION_SCORE_PER_MINUTE,
+        name: "Vision score/min",
+        summary: Summary::Median,
+        up_is_good: true,
+        percent: false,
+        roles: None,
+        extract: |game| Some(game.vision_score_per_minute),
+    },
+    &PerGame {
+        id: "solo_kills",
+        name: "Solo kills",
+        summary: Summary::Mean,
+        up_is_good: true,
+        percent: false,
+        roles: None,
+        extract: |game| Some(f64::from(game.solo_kills)),
+    },
+    &PerGame {
+        id: "solo_deaths",
+        name: "Solo deaths",
+        summary: Summary::Mean,
+        up_is_good: false,
+        percent: false,
+        roles: None,
+        extract: |game| Some(f64::from(game.solo_deaths)),
+    },
+    &PerGame {
+        id: KILL_PARTICIPATION,
+        name: "Kill participation",
+        summary: Summary::Mean,
+        up_is_good: true,
+        percent: true,
+        roles: None,
+        extract: |game| Some(game.kill_participation),
+    },
+    &Rate {
+        id: "fight_participation",
+        name: "Fight participation",
+        up_is_good: true,
+        counts: |game| (game.fights_participated, game.fights),
+    },
+    &Rate {
+        id: "fight_first_deaths",
+        name: "Died first in fights",
+        up_is_good: false,
+        counts: |game| (game.fights_died_first, game.fights_participated),
+    },
+    &Rate {
+        id: "fight_win_rate",
+        name: "Fight win rate",
+        up_is_good: true,
+        counts: |game| (game.fights_won, game.fights_participated),
+    },
+    &PerGame {
+        id: "cs_per_minute_at_10",
+        name: "CS/min @10",
+        summary: Summary::Median,
+        up_is_good: true,
+        percent: false,
+        roles: Some(LANING_ROLES),
+        extract: |game| game.cs_per_minute_at_10,
+    },
+    &PerGame {
+        id: "cs_diff_at_10",
+        name: "CS diff @10",
+        summary: Summary::Median,
+        up_is_good: true,
+        percent: false,
+        roles: Some(LANING_ROLES),
+        extract: |game| game.cs_diff_at_10.map(f64::from),
+    },
+    &PerGame {
+        id: "gold_diff_at_10",
+        name: "Gold diff @10",
+        summary: Summary::Median,
+        up_is_good: true,
+        percent: false,
+        roles: None,
+        extract: |game| game.gold_diff_at_10.map(f64::from),
+    },
+    &PerGame {
+        id: "level_diff_at_10",
+        name: "Level diff @10",
+        summary: Summary::Median,
+        up_is_good: true,
+        percent: false,
+        roles: None,
+        extract: |game| game.level_diff_at_10,
+    },
+    &PerGame {
+        id: "cs_per_minute_at_15",
+        name: "CS/min @15",
+        summary: Summary::Median,
+        up_is_good: true,
+        percent: false,
+        roles: Some(LANING_ROLES),
+        extract: |game| game.cs_per_minute_at_15,
+    },
+    &PerGame {
+        id: "cs_diff_at_15",
+        name: "CS diff @15",
+        summary: Summary::Median,
+        up_is_good: true,
+        percent: false,
+        roles: Some(LANING_ROLES),
+        extract: |game| game.cs_diff_at_15.map(f64::from),
+    },
+    &PerGame {
+        id: "gold_diff_at_15",
+        name: "Gold diff @15",
+        summary: Summary::Median,
+        up_is_good: true,
+        percent: false,
+        roles: None,
+        extract: |game| game.gold_diff_at_15.map(f64::from),
+    },
+    &PerGame {
+        id: "level_diff_at_15",
+        name: "Level diff @15",
+        summary: Summary::Median,
+        up_is_good: true,
+        percent: false,
+        roles: None,
+        extract: |game| game.level_diff_at_15,
+    },
+];
+
+/// Whether a metric is shown when looking at `role`. Everything is shown
+/// when looking at all roles together.
+pub fn is_shown(metric: &dyn Metric, role: Option<Role>) -> bool {
+    match (metric.roles(), role) {
+        (Some(roles), Some(role)) => roles.contains(&role),
+        _ => true,
+    }
+}
+
+/// Every built-in metric, followed by the custom ones.
+pub fn all_metrics<'a>(custom: &'a [CustomMetric]) -> impl Iterator<Item = &'a dyn Metric> {
+    METRICS
+        .iter()
+        .map(|&metric| metric as &'a dyn Metric)
+        .chain(custom.iter().map(|metric| metric as &'a dyn Metric))
+}
+
+pub fn find<'a>(id: &str, custom: &'a [CustomMetric]) -> Option<&'a dyn Metric> {
+    all_metrics(custom).find(|metric| metric.id() == id)
+}
+
+/// One metric summed up over a group of games.
+#[derive(Clone, Debug, Serialize)]
+pub struct MetricValue {
+    pub id: String,
+    pub name: String,
+    /// Whether the metric is shown for the role the games are in
+    pub shown: bool,
+    pub value: NumberWithOptionalDelta,
+    /// Among other players' games, higher being better, if there's a baseline
+    pub percentile: Option<u32>,
+    #[serde(skip)]
+    formatted: String,
+}
+
+impl MetricValue {
+    pub fn formatted(&self) -> &str {
+        &self.formatted
+    }
+
+    pub fn percentile_display(&self) -> Option<String> {
+        self.percentile
+            .map(|percentile| format!("{} percentile", ordinal(percentile)))
+    }
+}
+
+/// Every metric summed up over `games`.
+pub fn aggregate_all(
+    games: &[GameMetrics],
+    custom: &[CustomMetric],
+    role: Option<Role>,
+) -> Vec<MetricValue> {
+    all_metrics(custom)
+        .map(|metric| {
+            let value = metric.aggregate(games);
+            let formatted = if value.number.is_nan() {
+                "-".to_string()
+            } else {
+                metric.format(value.number)
+            };
+            MetricValue {
+                id: metric.id().to_string(),
+                name: metric.name().to_string(),
+                shown: is_shown(metric, role),
+                value,
+                percentile: None,
+                formatted,
+            }
+        })
+        .collect()
+}
+
+/// Every metric's value in each game, for the trend plots.
+pub fn per_game_values(
+    game: &GameMetrics,
+    custom: &[CustomMetric],
+) -> serde_json::Map<String, serde_json::Value> {
+    all_metrics(custom)
+        .map(|metric| (metric.id().to_string(), metric.extract(game).into()))
+        .collect()
+}
@@ -0,0 +1,128 @@
+use super::{
+    cs_per_minute, fights, frame_stats_at, get_legendary_buys, get_player, get_team,
+    infer_opponent, kill_participation, solo_deaths, solo_kills, team_share,
+    timeline_get_player_id,
+};
+use crate::riot_api::json::{self, Role};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Everything we compute for a single game. Flat, so it can be a CSV row.
+#[derive(Clone, Debug, Serialize)]
+pub struct GameMetrics {
+    pub match_id: String,
+    pub date: DateTime<Utc>,
+    pub patch: String,
+    pub champion: String,
+    pub role: Role,
+    /// `None` if we aren't sure enough who it was, and then the differences
+    /// to the opponent are `None` too
+    pub opponent: Option<String>,
+    /// How sure we are of the opponent, from 0 to 1
+    pub opponent_confidence: f64,
+    pub win: bool,
+    pub duration_minutes: i64,
+    pub kills: i32,
+    pub deaths: i32,
+    pub assists: i32,
+    pub kda: f64,
+    pub cs_per_minute: f64,
+    pub gold_share: f64,
+    pub champion_damage_share: f64,
+    pub objective_damage_share: f64,
+    pub vision_share: f64,
+    pub vision_score_per_minute: f64,
+    pub solo_kills: u32,
+    pub solo_deaths: u32,
+    pub kill_participation: f64,
+    pub fights: u32,
+    pub fights_participated: u32,
+    pub fights_died_first: u32,
+    pub fights_won: u32,
+    pub cs_per_minute_at_10: Option<f64>,
+    pub gold_diff_at_10: Option<i32>,
+    pub cs_diff_at_10: Option<i32>,
+    pub level_diff_at_10: Option<f64>,
+    pub cs_per_minute_at_15: Option<f64>,
+    pub gold_diff_at_15: Option<i32>,
+    pub cs_diff_at_15: Option<i32>,
+    pub level_diff_at_15: Option<f64>,
+    pub first_legendary_seconds: Option<i64>,
+    /// Values of the custom metrics, if they were evaluated
+    #[serde(skip)]
+    pub custom: Vec<Option<f64>>,
+}
+
+pub fn game_metrics(m: &json::Match, timeline: &json::Timeline, puuid: &str) -> GameMetrics {
+    let player = get_player(m, puuid);
+    let team = get_team(m, player);
+    let inferred = infer_opponent(m, Some(timeline), player);
+    let opponent = inferred.is_reliable().then_some(inferred.participant);
+    let timeline_player_id = timeline_get_player_id(timeline, puuid);
+    let timeline_opponent_id =
+        opponent.map(|opponent| timeline_get_player_id(timeline, &opponent.puuid));
+
+    let team_per_participant = m
+        .info
+        .participants
+        .iter()
+        .map(|p| (timeline_get_player_id(timeline, &p.puuid), p.team_id))
+        .collect::<HashMap<_, _>>();
+    let fights = fights::find_fights(&timeline.info.frames);
+    let fight_counts = fights::count_fights(&fights, timeline_player_id, &team_per_participant);
+
+    let at = |minute| {
+        frame_stats_at(
+            &timeline.info.frames,
+            timeline_player_id,
+            timeline_opponent_id,
+            TimeDelta::minutes(minute),
+        )
+    };
+    let at_10 = at(10);
+    let at_15 = at(15);
+
+    let deaths = f64::from(player.deaths).max(1.0);
+    let game_minutes = f64::from(i32::try_from(m.info.game_duration.num_minutes()).unwrap());
+    GameMetrics {
+        match_id: m.metadata.match_id.clone(),
+        date: m.info.game_start_timestamp,
+        patch: m.info.patch(),
+        champion: player.champion_name.clone(),
+        role: player.team_position,
+        opponent: opponent.map(|opponent| opponent.champion_name.clone()),
+        opponent_confidence: inferred.confidence,
+        win: player.win,
+        duration_minutes: m.info.game_duration.num_minutes(),
+        kills: player.kills,
+        deaths: player.deaths,
+        assists: player.assists,
+        kda: (f64::from(player.kills) + f64::from(player.assists)) / deaths,
+        cs_per_minute: cs_per_minute(m, player),
+        gold_share: team_share(player, &team, |p| p.gold_earned),
+        champion_damage_share: team_share(player, &team, |p| p.total_damage_dealt_to_champions),
+        objective_damage_share: team_share(player, &team, |p| p.damage_dealt_to_objectives),
+        vision_share: team_share(player, &team, |p| p.vision_score),
+        vision_score_per_minute: f64::from(player.vision_score) / game_minutes,
+        solo_kills: solo_kills(timeline, timeline_player_id),
+        solo_deaths: solo_deaths(timeline, &team, timeline_player_id, puuid),
+        kill_participation: kill_participation(player, &team),
+        fights: fight_counts.fights,
+        fights_participated: fight_counts.participated,
+        fights_died_first: fight_counts.died_first,
+        fights_won: fight_counts.won,
+        cs_per_minute_at_10: at_10.as_ref().map(|s| s.cs_per_minute),
+        gold_diff_at_10: at_10.as_ref().and_then(|s| s.gold_diff),
+        cs_diff_at_10: at_10.as_ref().and_then(|s| s.cs_diff),
+        level_diff_at_10: at_10.as_ref().and_then(|s| s.level_diff),
+        cs_per_minute_at_15: at_15.as_ref().map(|s| s.cs_per_minute),
+        gold_diff_at_15: at_15.as_ref().and_then(|s| s.gold_diff),
+        cs_diff_at_15: at_15.as_ref().and_then(|s| s.cs_diff),
+        level_diff_at_15: at_15.as_ref().and_then(|s| s.level_diff),
+        first_legendary_seconds: get_legendary_buys(&timeline.info.frames, timeline_player_id)
+            .first()
+            .map(TimeDelta::num_seconds),
+        custom: vec![],
+    }
+}
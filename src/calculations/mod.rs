@@ -0,0 +1,1805 @@
+use crate::{
+    normalize_champion_name,
+    riot_api::{
+        fetch_matches_by_id, get_match_ids_by_tournament_code, get_puuid_and_canonical_name,
+        json::{self, Match, Role},
+        update_match_history,
+    },
+    LeagueRegion, Player, Result, State, CHAMP_NAMES_BY_ID,
+};
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeDelta, Utc};
+use itertools::{Itertools, Position};
+use log::debug;
+use ordered_float::OrderedFloat;
+use serde::Serialize;
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt::{self, Display, Formatter, Write},
+    ops::RangeInclusive,
+};
+
+mod bans;
+mod baseline;
+mod custom_metric;
+mod distribution;
+mod duos;
+mod exclusions;
+mod expression;
+mod fights;
+mod goals;
+mod history;
+mod match_detail;
+mod metric;
+mod metrics;
+mod opponent;
+mod pool;
+mod rank;
+mod roster;
+mod significance;
+mod tags;
+mod team_report;
+
+pub use bans::BanAnalysis;
+pub use baseline::{invalidate_baselines, BaselineCache};
+pub use custom_metric::{try_expression, FUNCTIONS as CUSTOM_METRIC_FUNCTIONS};
+pub use distribution::{Distribution, Summary};
+pub use duos::DuoStats;
+pub use exclusions::{exclusion_reason, ExclusionReason};
+pub use goals::{goal_metrics, GoalProgress};
+pub use history::{HistoryFilter, HistoryRow};
+pub use match_detail::{match_detail, MatchDetail};
+pub use metric::MetricValue;
+pub use metrics::GameMetrics;
+pub use opponent::{infer_opponent, Opponent, OpponentSource};
+pub use pool::RolePool;
+pub use rank::{current_rank, rank_chart_data};
+pub use roster::RosterAggregates;
+pub use tags::{known_tags, TagFilter};
+pub use team_report::TeamReport;
+
+pub const NUM_WEEKS: i64 = 4;
+// With fewer games than this, a few more games can change the numbers a lot.
+pub const MIN_SAMPLE_GAMES: u32 = 10;
+// When a period has no start or patch, how far back it is taken to go.
+const PERIOD_LOOKBACK_WEEKS: i64 = 12;
+const MINUTES_AT: RangeInclusive<u32> = 2..=20;
+// Minutes that get their own metrics, rather than only a graph.
+const KEY_MINUTES: [u32; 2] = [10, 15];
+// Ranked solo/duo, the only queue in fetched match histories. Scrims and
+// imported games are stored for everyone in them, so they are left out of
+// stats unless they're asked for.
+const SOLO_QUEUE_ID: i32 = 420;
+const XP_LEVELS: [i32; 17] = [
+    280, 380, 480, 580, 680, 780, 880, 980, 1080, 1180, 1280, 1380, 1480, 1580, 1680, 1780, 1880,
+];
+include!(concat!(env!("OUT_DIR"), "/codegen-item-ranks.rs"));
+
+struct StatsAtMinuteGathering {
+    cs_per_minute: f64,
+    // The differences are `None` when we aren't sure who the opponent was
+    gold_diff: Option<i32>,
+    cs_diff: Option<i32>,
+    level_diff: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct StatsAtMinute {
+    pub cs_per_minute: NumberWithOptionalDelta,
+    pub gold_diff: NumberWithOptionalDelta,
+    pub cs_diff: NumberWithOptionalDelta,
+    pub level_diff: NumberWithOptionalDelta,
+}
+
+impl StatsAtMinute {
+    fn compare_to(&mut self, other: &Self) {
+        self.cs_per_minute.compare_to(&other.cs_per_minute);
+        self.gold_diff.compare_to(&other.gold_diff);
+        self.cs_diff.compare_to(&other.cs_diff);
+        self.level_diff.compare_to(&other.level_diff);
+    }
+}
+
+/// A range of time, a patch, or both, to restrict matches to.
+#[derive(Clone, Debug, Default)]
+pub struct Period {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub patch: Option<String>,
+}
+
+impl Period {
+    pub fn since(from: DateTime<Utc>) -> Self {
+        Self {
+            from: Some(from),
+            ..Self::default()
+        }
+    }
+
+    /// How far back the match history has to go to cover this period.
+    pub fn start(&self) -> DateTime<Utc> {
+        self.from
+            .or_else(|| self.patch.as_deref().and_then(patch_released_after))
+            .unwrap_or_else(|| Utc::now() - chrono::Duration::weeks(PERIOD_LOOKBACK_WEEKS))
+    }
+
+    fn contains(&self, m: &Match) -> bool {
+        let start = m.info.game_start_timestamp;
+        self.from.is_none_or(|from| start > from)
+            && self.to.is_none_or(|to| start < to)
+            && self
+                .patch
+                .as_ref()
+                .is_none_or(|patch| m.info.patch() == *patch)
+    }
+}
+
+/// A time no later than the release of `patch`, like `14.20`. Season N
+/// patches come out in year 2010 + N, starting in January, and at least two
+/// weeks apart.
+fn patch_released_after(patch: &str) -> Option<DateTime<Utc>> {
+    let (season, number) = patch.split_once('.')?;
+    let season = season.parse::<i32>().ok()?;
+    let number = number.parse::<i64>().ok().filter(|number| *number >= 1)?;
+    let new_year = NaiveDate::from_ymd_opt(2010 + season, 1, 1)?;
+    Some(new_year.and_time(NaiveTime::MIN).and_utc() + chrono::Duration::weeks(2 * (number - 1)))
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, strum::EnumIter, strum::Display)]
+pub enum Side {
+    Blue,
+    Red,
+}
+
+#[derive(Default)]
+struct WeekStatsGathering {
+    wins: u32,
+    losses: u32,
+    stats_at: HashMap<u32, Vec<StatsAtMinuteGathering>>,
+    heatmap_data: HeatMapDataGathering,
+    roles: Vec<Role>,
+    roles_sides: Vec<(Role, Side)>,
+    _wards_placed: Vec<(Position, TimeDelta)>,
+    legendary_item_buy_times: Vec<Vec<TimeDelta>>,
+    games: Vec<GameMetrics>,
+    custom_metrics: Vec<custom_metric::CustomMetric>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NumberWithOptionalDelta {
+    pub number: f64,
+    pub delta: Option<f64>,
+    pub up_is_good: bool,
+    /// Whether the delta is unlikely to be chance, given the per game values
+    pub significant: bool,
+    /// How `number` was calculated from the per game values, if it was
+    pub summary: Option<Summary>,
+    pub distribution: Option<Distribution>,
+    #[serde(skip)]
+    samples: Vec<f64>,
+}
+
+impl From<f64> for NumberWithOptionalDelta {
+    fn from(number: f64) -> Self {
+        let number = (number * 10.0).round() / 10.0;
+        Self {
+            number,
+            delta: None,
+            up_is_good: true,
+            significant: false,
+            summary: None,
+            distribution: None,
+            samples: vec![],
+        }
+    }
+}
+
+impl NumberWithOptionalDelta {
+    /// Summarizes per game values as their mean or median, keeping how they
+    /// are spread.
+    fn summarized<T: Into<f64>>(values: impl IntoIterator<Item = T>, summary: Summary) -> Self {
+        let samples = values.into_iter().map(Into::into).collect::<Vec<f64>>();
+        let number = match summary {
+            // E.g. no game lasted long enough to have stats at 15 minutes
+            _ if samples.is_empty() => f64::NAN,
+            Summary::Mean => average(&samples),
+            Summary::Median => median(&samples),
+        };
+        Self {
+            summary: Some(summary),
+            distribution: Distribution::from_samples(&samples),
+            samples,
+            ..Self::from(number)
+        }
+    }
+    /// An inline box plot of the per game values, if there are any.
+    pub fn box_plot(&self) -> Option<String> {
+        Some(self.distribution.as_ref()?.box_plot(self.summary?))
+    }
+    fn up_is_bad(self) -> Self {
+        Self {
+            up_is_good: false,
+            ..self
+        }
+    }
+    /// Keeps the per game values, which are needed to tell whether a delta
+    /// is significant.
+    fn with_samples<T: Into<f64>>(mut self, samples: impl IntoIterator<Item = T>) -> Self {
+        self.samples = samples.into_iter().map(Into::into).collect();
+        self
+    }
+    fn compare_to(&mut self, other: &Self) {
+        self.delta = Some(self.number - other.number);
+        self.significant = significance::is_significant(&self.samples, &other.samples);
+    }
+    pub fn has_visible_diff(&self) -> Ordering {
+        let mut factor = 10.0;
+        if !self.significant {
+            return Ordering::Equal;
+        }
+        if let Some(delta) = self.delta {
+            if !self.up_is_good {
+                factor = -factor;
+            }
+            let truncated = (delta * factor).round();
+            if truncated.abs() >= 1.0 {
+                return truncated.partial_cmp(&0.0).unwrap();
+            }
+        }
+        Ordering::Equal
+    }
+}
+
+impl Display for NumberWithOptionalDelta {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.number.fmt(f)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DisplayTimeDelta {
+    pub time: TimeDelta,
+    pub delta: Option<TimeDelta>,
+    pub down_is_good: bool,
+    pub significant: bool,
+    /// Per game values in seconds
+    samples: Vec<f64>,
+}
+
+impl From<TimeDelta> for DisplayTimeDelta {
+    fn from(time: TimeDelta) -> Self {
+        Self {
+            time,
+            delta: None,
+            down_is_good: true,
+            significant: false,
+            samples: vec![],
+        }
+    }
+}
+
+impl DisplayTimeDelta {
+    #[allow(clippy::cast_precision_loss)]
+    fn with_samples(mut self, samples: &[TimeDelta]) -> Self {
+        self.samples = samples.iter().map(|t| t.num_seconds() as f64).collect();
+        self
+    }
+    fn compare_to(&mut self, other: &Self) {
+        self.delta = Some(self.time - other.time);
+        self.significant = significance::is_significant(&self.samples, &other.samples);
+    }
+    pub fn has_visible_diff(&self) -> Ordering {
+        if !self.significant {
+            return Ordering::Equal;
+        }
+        if let Some(mut delta) = self.delta {
+            if self.down_is_good {
+                delta = -delta;
+            }
+            if delta.num_seconds().abs() >= 1 {
+                return delta.partial_cmp(&TimeDelta::zero()).unwrap();
+            }
+        }
+        Ordering::Equal
+    }
+    pub fn display_diff(&self) -> String {
+        if let Some(delta) = self.delta {
+            let mut seconds = delta.num_seconds();
+            let mut result = String::new();
+            result.push(if seconds < 0 { '-' } else { '+' });
+            seconds = seconds.abs();
+            let hours = seconds / 3600;
+            seconds %= 3600;
+            let minutes = seconds / 60;
+            seconds %= 60;
+            if hours > 0 {
+                write!(result, "{hours}h").unwrap();
+            }
+            if minutes > 0 {
+                write!(result, "{minutes}m").unwrap();
+            }
+            write!(result, "{seconds}s").unwrap();
+            result
+        } else {
+            String::new()
+        }
+    }
+}
+
+impl Display for DisplayTimeDelta {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut seconds = self.time.num_seconds();
+        let hours = seconds / 3600;
+        seconds %= 3600;
+        let minutes = seconds / 60;
+        seconds %= 60;
+        if hours > 0 {
+            write!(f, "{hours}h")?;
+        }
+        if minutes > 0 {
+            write!(f, "{minutes}m")?;
+        }
+        write!(f, "{seconds}s")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GroupStats {
+    pub title: String,
+    pub id: String,
+    pub wins: u32,
+    pub losses: u32,
+    pub games_played: u32,
+    /// Every metric in `METRICS`, in the same order, then the custom ones
+    pub metrics: Vec<MetricValue>,
+    pub at_minute_stats: Vec<(u32, StatsAtMinute)>,
+    pub previous_at_minute_stats: Option<Vec<(u32, StatsAtMinute)>>,
+    pub heatmap_data: HeatMapData,
+    /// JSON array of every game's metrics, oldest first, for the trend plots
+    pub trend_data: String,
+    #[allow(clippy::type_complexity)]
+    pub legendary_buy_times: Vec<DisplayTimeDelta>,
+    pub per_role_per_champ: Vec<(Role, DisplayChampMatches)>,
+    pub per_role_per_enemy: Vec<(Role, DisplayChampMatches)>,
+    pub champion_pools: Vec<RolePool>,
+    pub duos: Vec<DuoStats>,
+    pub goals: Vec<GoalProgress>,
+    pub games: Vec<GameSummary>,
+    /// How many games were left out of the stats, and why
+    pub excluded: Vec<(ExclusionReason, usize)>,
+    /// What the metrics' percentiles are among, if there are any
+    pub baseline: Option<String>,
+    /// Solo queue LP won or lost in the period, over all games
+    pub lp_delta: Option<i32>,
+}
+
+/// Just enough about one game to list it and link to its details.
+#[derive(Clone, Debug)]
+pub struct GameSummary {
+    pub match_id: String,
+    pub puuid: String,
+    pub start: DateTime<Utc>,
+    pub champion: String,
+    pub role: Role,
+    pub win: bool,
+    pub kills: i32,
+    pub deaths: i32,
+    pub assists: i32,
+}
+
+impl GameSummary {
+    fn new(m: &Match, puuid: &str) -> Self {
+        let player = get_player(m, puuid);
+        Self {
+            match_id: m.metadata.match_id.clone(),
+            puuid: puuid.to_string(),
+            start: m.info.game_start_timestamp,
+            champion: player.champion_name.clone(),
+            role: player.team_position,
+            win: player.win,
+            kills: player.kills,
+            deaths: player.deaths,
+            assists: player.assists,
+        }
+    }
+}
+
+impl GroupStats {
+    pub fn is_small_sample(&self) -> bool {
+        self.games_played < MIN_SAMPLE_GAMES
+    }
+
+    pub fn lp_delta_display(&self) -> Option<String> {
+        self.lp_delta.map(|delta| format!("{delta:+} LP"))
+    }
+
+    pub fn excluded_count(&self) -> usize {
+        self.excluded.iter().map(|(_, count)| count).sum()
+    }
+
+    /// Built-in metrics are always gathered, whatever the role.
+    fn built_in(&self, id: &str) -> &NumberWithOptionalDelta {
+        let metric = self.metrics.iter().find(|metric| metric.id == id);
+        &metric.expect("built-in metrics are always gathered").value
+    }
+
+    pub fn winrate(&self) -> &NumberWithOptionalDelta {
+        self.built_in(metric::WINRATE)
+    }
+    pub fn kills(&self) -> &NumberWithOptionalDelta {
+        self.built_in(metric::KILLS)
+    }
+    pub fn deaths(&self) -> &NumberWithOptionalDelta {
+        self.built_in(metric::DEATHS)
+    }
+    pub fn assists(&self) -> &NumberWithOptionalDelta {
+        self.built_in(metric::ASSISTS)
+    }
+    pub fn kda(&self) -> &NumberWithOptionalDelta {
+        self.built_in(metric::KDA)
+    }
+    pub fn cs_per_minute(&self) -> &NumberWithOptionalDelta {
+        self.built_in(metric::CS_PER_MINUTE)
+    }
+    pub fn gold_share(&self) -> &NumberWithOptionalDelta {
+        self.built_in(metric::GOLD_SHARE)
+    }
+    pub fn kill_participation(&self) -> &NumberWithOptionalDelta {
+        self.built_in(metric::KILL_PARTICIPATION)
+    }
+    pub fn vision_score_per_minute(&self) -> &NumberWithOptionalDelta {
+        self.built_in(metric::VISION_SCORE_PER_MINUTE)
+    }
+
+    pub fn shown_metrics(&self) -> impl Iterator<Item = &MetricValue> {
+        self.metrics.iter().filter(|metric| metric.shown)
+    }
+
+    pub fn compare_to(&mut self, other: &Self) {
+        for metric in &mut self.metrics {
+            if let Some(other_metric) = other.metrics.iter().find(|m| m.id == metric.id) {
+                metric.value.compare_to(&other_metric.value);
+            }
+        }
+        for (minute, stats_at) in &mut self.at_minute_stats {
+            if let Some((_, other_stats_at)) =
+                other.at_minute_stats.iter().find(|(m, _)| m == minute)
+            {
+                stats_at.compare_to(other_stats_at);
+            }
+        }
+        self.legendary_buy_times
+            .iter_mut()
+            .zip(&other.legendary_buy_times)
+            .for_each(|(buy_times, other_buy_times)| {
+                buy_times.compare_to(other_buy_times);
+            });
+        for (role, per_champ) in &mut self.per_role_per_champ {
+            if let Some((_, other_per_champ)) =
+                other.per_role_per_champ.iter().find(|(r, _)| r == role)
+            {
+                for (champ, _, champ_stats) in per_champ {
+                    if let Some((_, _, other_champ_stats)) =
+                        other_per_champ.iter().find(|(c, _, _)| c == champ)
+                    {
+                        champ_stats.compare_to(other_champ_stats);
+                    }
+                }
+            }
+        }
+        for (role, per_champ) in &mut self.per_role_per_enemy {
+            if let Some((_, other_per_champ)) =
+                other.per_role_per_enemy.iter().find(|(r, _)| r == role)
+            {
+                for (champ, _, champ_stats) in per_champ {
+                    if let Some((_, _, other_champ_stats)) =
+                        other_per_champ.iter().find(|(c, _, _)| c == champ)
+                    {
+                        champ_stats.compare_to(other_champ_stats);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub type HeatMapData = Vec<(Role, Side, usize, String)>;
+type HeatMapDataGathering = HashMap<(Role, Side), HashMap<i64, Vec<json::Point>>>;
+type ChampMatches<'a> = Vec<(String, Vec<&'a Match>)>;
+type DisplayChampMatches = Vec<(String, String, GroupStats)>;
+
+fn median<'a, T>(values: impl IntoIterator<Item = &'a T>) -> f64
+where
+    T: Copy + Into<f64> + 'a,
+{
+    let mut values = values
+        .into_iter()
+        .map(|&value| OrderedFloat::from(value.into()))
+        .collect::<Vec<_>>();
+    if values.is_empty() {
+        return f64::NAN;
+    }
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        (values[mid - 1].into_inner() + values[mid].into_inner()) / 2.0
+    } else {
+        values[mid].into_inner()
+    }
+}
+
+fn median_td<'a>(values: impl IntoIterator<Item = &'a TimeDelta>) -> TimeDelta {
+    let mut values = values
+        .into_iter()
+        .map(TimeDelta::num_seconds)
+        .collect::<Vec<_>>();
+    values.sort_unstable();
+    let mid = values.len() / 2;
+    if values.len() % 2 == 0 {
+        TimeDelta::seconds((values[mid - 1] + values[mid]) / 2)
+    } else {
+        TimeDelta::seconds(values[mid])
+    }
+}
+
+fn average<'a, T>(values: impl IntoIterator<Item = &'a T>) -> f64
+where
+    T: Copy + Into<f64> + 'a,
+{
+    let mut sum = 0.0;
+    let mut count = 0;
+    for value in values {
+        sum += Into::<f64>::into(*value);
+        count += 1;
+    }
+    sum / f64::from(count)
+}
+
+fn percentage(part: u32, total: u32) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    100.0 * f64::from(part) / f64::from(total)
+}
+
+/// Turns a count out of a total into one value per game or fight, 100 if it
+/// counted and 0 if not, so rates can be tested like the other stats.
+fn rate_samples(part: u32, total: u32) -> impl Iterator<Item = f64> {
+    std::iter::repeat_n(100.0, part as usize).chain(std::iter::repeat_n(
+        0.0,
+        total.saturating_sub(part) as usize,
+    ))
+}
+
+fn level_for_xp(mut xp: i32) -> f64 {
+    let mut level = 1.0;
+    // Because we use the limited array above, the result will never be more than 18.0
+    for &xp_level in &XP_LEVELS {
+        if xp >= xp_level {
+            level += 1.0;
+        } else {
+            level += f64::from(xp) / f64::from(xp_level);
+            break;
+        }
+        xp -= xp_level;
+    }
+    level
+}
+
+fn timeline_get_player_id(timeline: &json::Timeline, puuid: &str) -> usize {
+    timeline
+        .info
+        .participants
+        .iter()
+        .find(|p| p.puuid == puuid)
+        .unwrap()
+        .participant_id
+}
+
+fn champion_name_by_id(champion_id: i32) -> String {
+    CHAMP_NAMES_BY_ID
+        .get(&champion_id)
+        .map_or_else(|| format!("champion {champion_id}"), ToString::to_string)
+}
+
+fn get_player<'a>(match_info: &'a json::Match, puuid: &'a str) -> &'a json::Participant {
+    match_info
+        .info
+        .participants
+        .iter()
+        .find(|p| p.puuid == puuid)
+        .unwrap()
+}
+
+/// The player's lane opponent, see `infer_opponent`, if we're sure enough of
+/// who it was. Without the timeline only Smite and the positions are used.
+fn get_opponent<'a>(
+    match_info: &'a json::Match,
+    timeline: Option<&json::Timeline>,
+    player: &'a json::Participant,
+) -> Option<&'a json::Participant> {
+    Some(infer_opponent(match_info, timeline, player))
+        .filter(Opponent::is_reliable)
+        .map(|opponent| opponent.participant)
+}
+
+fn get_team<'a>(
+    match_info: &'a json::Match,
+    player: &'a json::Participant,
+) -> Vec<&'a json::Participant> {
+    match_info
+        .info
+        .participants
+        .iter()
+        .filter(|p| p.team_id == player.team_id)
+        .collect()
+}
+
+/// The player's stats at `timestamp`. The differences to the opponent are
+/// left out if we don't know who the opponent was.
+fn frame_stats_at(
+    frames: &[json::Frame],
+    player: usize,
+    opponent: Option<usize>,
+    timestamp: TimeDelta,
+) -> Option<StatsAtMinuteGathering> {
+    let frame = frames.iter().find(|f| f.timestamp >= timestamp)?;
+    let player_frame = frame.participant_frames.get(&player)?;
+    let cpm = (f64::from(player_frame.minions_killed)
+        + f64::from(player_frame.jungle_minions_killed))
+        / f64::from(i32::try_from(timestamp.num_minutes()).unwrap());
+    let opponent_frame = opponent.and_then(|opponent| frame.participant_frames.get(&opponent));
+    Some(StatsAtMinuteGathering {
+        cs_per_minute: cpm,
+        gold_diff: opponent_frame.map(|o| player_frame.total_gold - o.total_gold),
+        cs_diff: opponent_frame.map(|o| player_frame.minions_killed - o.minions_killed),
+        level_diff: opponent_frame.map(|o| level_for_xp(player_frame.xp) - level_for_xp(o.xp)),
+    })
+}
+
+fn cs_per_minute(m: &json::Match, player: &json::Participant) -> f64 {
+    (f64::from(player.total_minions_killed)
+        + f64::from(player.total_ally_jungle_minions_killed)
+        + f64::from(player.total_enemy_jungle_minions_killed))
+        / f64::from(i32::try_from(m.info.game_duration.num_minutes()).unwrap())
+}
+
+fn team_share<'a>(
+    player: &'a json::Participant,
+    team: impl IntoIterator<Item = &'a &'a json::Participant>,
+    field: impl Fn(&'a json::Participant) -> i32,
+) -> f64 {
+    let team_field = team.into_iter().map(|p| f64::from(field(p))).sum::<f64>();
+    100.0 * f64::from(field(player)) / team_field
+}
+
+fn kill_participation(player: &json::Participant, team: &[&json::Participant]) -> f64 {
+    let team_kills = team.iter().map(|p| p.kills).sum::<i32>();
+    if team_kills == 0 {
+        return 0.0;
+    }
+    100.0 * f64::from(player.kills + player.assists) / f64::from(team_kills)
+}
+
+fn solo_kills(timeline: &json::Timeline, player_id: usize) -> u32 {
+    timeline
+        .info
+        .frames
+        .iter()
+        .map(|f| {
+            u32::try_from(
+                f.events
+                    .iter()
+                    .filter(|e| {
+                        if let json::Event::ChampionKill(kill) = e {
+                            kill.killer_id == player_id && kill.assisting_participant_ids.is_empty()
+                        } else {
+                            false
+                        }
+                    })
+                    .count(),
+            )
+            .unwrap()
+        })
+        .sum::<u32>()
+}
+
+fn solo_deaths<'a>(
+    timeline: &'a json::Timeline,
+    team: impl IntoIterator<Item = &'a &'a json::Participant>,
+    player_id: usize,
+    puuid: &'a str,
+) -> u32 {
+    let team_other_player_ids = team
+        .into_iter()
+        .filter_map(|p| {
+            if p.puuid == puuid {
+                None
+            } else {
+                Some(timeline_get_player_id(timeline, &p.puuid))
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let deaths = timeline
+        .info
+        .frames
+        .iter()
+        .flat_map(|f| {
+            f.events.iter().filter_map(|e| {
+                if let json::Event::ChampionKill(kill) = e {
+                    if kill.victim_id == player_id {
+                        return Some((kill.timestamp, kill.position));
+                    }
+                }
+                None
+            })
+        })
+        .collect::<Vec<_>>();
+
+    deaths
+        .iter()
+        .filter(|(timestamp, position)| {
+            // Get the frames before and after the kill. Both should exist
+            // because there's the start and end of the game frames.
+            //
+            // Since we don't want to go through the entire timeline, just use
+            // position() and base both indexes on that.
+            let after_index = timeline
+                .info
+                .frames
+                .iter()
+                .position(|f| f.timestamp > *timestamp)
+                .unwrap();
+            let frame_after = &timeline.info.frames[after_index];
+            let frame_before = &timeline.info.frames[after_index - 1];
+            // Check if the player was the only one in the area
+            for frame in [frame_before, frame_after] {
+                for pid in &team_other_player_ids {
+                    let other_player = frame.participant_frames.get(pid).unwrap();
+                    if other_player.position.distance(*position) < 4000.0 {
+                        return false;
+                    }
+                }
+            }
+            true
+        })
+        .count()
+        .try_into()
+        .unwrap()
+}
+
+fn gather_stats<'a>(
+    state: &State,
+    matches: impl IntoIterator<Item = &'a &'a json::Match>,
+    puuid: &str,
+) -> WeekStatsGathering {
+    let gathering = WeekStatsGathering {
+        custom_metrics: custom_metric::load(state),
+        ..WeekStatsGathering::default()
+    };
+    matches.into_iter().fold(gathering, |mut stats, m| {
+        let timeline = state.timeline_per_match.get(&m.metadata.match_id).unwrap();
+        let player = get_player(m, puuid);
+        let opponent = get_opponent(m, Some(&timeline), player);
+        let mut metrics = metrics::game_metrics(m, &timeline, puuid);
+        metrics.custom = custom_metric::evaluate_all(&stats.custom_metrics, m, &timeline, puuid);
+
+        if metrics.win {
+            stats.wins += 1;
+        } else {
+            stats.losses += 1;
+        }
+
+        let timeline_player_id = timeline_get_player_id(&timeline, puuid);
+        let timeline_opponent_id =
+            opponent.map(|opponent| timeline_get_player_id(&timeline, &opponent.puuid));
+
+        for minute in MINUTES_AT {
+            let stats_at = frame_stats_at(
+                &timeline.info.frames,
+                timeline_player_id,
+                timeline_opponent_id,
+                TimeDelta::minutes(i64::from(minute)),
+            );
+            if let Some(stats_at) = stats_at {
+                stats.stats_at.entry(minute).or_default().push(stats_at);
+            }
+        }
+
+        let side = match player.team_id {
+            100 => Side::Blue,
+            200 => Side::Red,
+            _ => unreachable!(),
+        };
+        let role = player.team_position;
+        stats.roles.push(role);
+        stats.roles_sides.push((role, side));
+        let heatmap_data = stats.heatmap_data.entry((role, side)).or_default();
+        for frame in &timeline.info.frames {
+            let minute = frame.timestamp.num_minutes();
+            let mut pos = frame
+                .participant_frames
+                .get(&timeline_player_id)
+                .unwrap()
+                .position;
+            pos.x /= 29;
+            pos.y /= 29;
+            pos.y = 512 - pos.y;
+            heatmap_data.entry(minute).or_default().push(pos);
+        }
+
+        add_legendary_buys(&mut stats, &timeline, timeline_player_id);
+        stats.games.push(metrics);
+        stats
+    })
+}
+
+fn get_legendary_buys<'a>(
+    frames: impl IntoIterator<Item = &'a json::Frame>,
+    timeline_player_id: usize,
+) -> Vec<TimeDelta> {
+    // Get timestamps of all legendary buys and sells
+    let int = frames
+        .into_iter()
+        .flat_map(|f| {
+            f.events.iter().filter_map(|e| {
+                if let json::Event::ItemPurchased {
+                    item_id,
+                    participant_id,
+                    timestamp,
+                } = e
+                {
+                    if *participant_id == timeline_player_id
+                        && ITEM_RANKS.get(item_id) == Some(&json::ItemType::Legendary)
+                    {
+                        return Some((*timestamp, true));
+                    }
+                }
+                if let json::Event::ItemSold {
+                    item_id,
+                    participant_id,
+                    timestamp,
+                } = e
+                {
+                    if *participant_id == timeline_player_id
+                        && ITEM_RANKS.get(item_id) == Some(&json::ItemType::Legendary)
+                    {
+                        return Some((*timestamp, false));
+                    }
+                }
+                if let json::Event::ItemUndo {
+                    after_id: _,
+                    before_id,
+                    gold_gain: _,
+                    participant_id,
+                    timestamp,
+                } = e
+                {
+                    if *participant_id == timeline_player_id
+                        && ITEM_RANKS.get(before_id) == Some(&json::ItemType::Legendary)
+                    {
+                        return Some((*timestamp, false));
+                    }
+                }
+                None
+            })
+        })
+        .collect::<Vec<_>>();
+    // Then remove the buys that are followed by a sell
+    int.into_iter()
+        .fold(vec![], |mut legendary_buys, (timestamp, is_buy)| {
+            if is_buy {
+                legendary_buys.push(timestamp);
+            } else {
+                // Can't sell a legendary item if you haven't bought one, so this
+                // should always work.
+                legendary_buys.pop().unwrap();
+            }
+            legendary_buys
+        })
+}
+
+fn add_legendary_buys(
+    stats: &mut WeekStatsGathering,
+    timeline: &json::Timeline,
+    timeline_player_id: usize,
+) {
+    let this_games_buys = get_legendary_buys(&timeline.info.frames, timeline_player_id);
+    while stats.legendary_item_buy_times.len() < this_games_buys.len() {
+        stats.legendary_item_buy_times.push(vec![]);
+    }
+    stats
+        .legendary_item_buy_times
+        .iter_mut()
+        .zip(this_games_buys)
+        .for_each(|(buys, timestamp)| {
+            buys.push(timestamp);
+        });
+}
+
+fn convert_stats(title: &str, gathered: WeekStatsGathering, role: Option<Role>) -> GroupStats {
+    let at_minute_stats = MINUTES_AT
+        .filter_map(|minute| {
+            let stats_at = gathered.stats_at.get(&minute)?;
+            let cs_per_minute = NumberWithOptionalDelta::summarized(
+                stats_at.iter().map(|s| s.cs_per_minute),
+                Summary::Median,
+            );
+            let gold_diff = NumberWithOptionalDelta::summarized(
+                stats_at.iter().filter_map(|s| s.gold_diff),
+                Summary::Median,
+            );
+            let cs_diff = NumberWithOptionalDelta::summarized(
+                stats_at.iter().filter_map(|s| s.cs_diff),
+                Summary::Median,
+            );
+            let level_diff = NumberWithOptionalDelta::summarized(
+                stats_at.iter().filter_map(|s| s.level_diff),
+                Summary::Median,
+            );
+
+            Some((
+                minute,
+                StatsAtMinute {
+                    cs_per_minute,
+                    gold_diff,
+                    cs_diff,
+                    level_diff,
+                },
+            ))
+        })
+        .collect();
+
+    let role_counts = gathered.roles.into_iter().counts();
+    let role_side_counts = gathered.roles_sides.into_iter().counts();
+    let heatmap_data = gathered
+        .heatmap_data
+        .into_iter()
+        .filter_map(|((role, side), data)| {
+            let count = *role_side_counts.get(&(role, side)).unwrap();
+            if role == Role::None {
+                None
+            } else {
+                Some((role, side, count, serde_json::to_string(&data).unwrap()))
+            }
+        })
+        .sorted_unstable_by(|(role1, side1, _, _), (role2, side2, _, _)| {
+            let role1 = role_counts.get(role1).unwrap();
+            let &role2 = role_counts.get(role2).unwrap();
+            // Reverse to get most played at the top
+            role2.cmp(role1).then(match (side1, side2) {
+                (Side::Blue, Side::Red) => Ordering::Less,
+                (Side::Red, Side::Blue) => Ordering::Greater,
+                _ => Ordering::Equal,
+            })
+        })
+        .collect();
+    let trend_data = serde_json::to_string(
+        &gathered
+            .games
+            .iter()
+            .sorted_by_key(|game| game.date)
+            .map(|game| {
+                serde_json::json!({
+                    "date": game.date,
+                    "champion": game.champion,
+                    "opponent": game.opponent,
+                    "win": game.win,
+                    "values": metric::per_game_values(game, &gathered.custom_metrics),
+                })
+            })
+            .collect::<Vec<_>>(),
+    )
+    .unwrap();
+    let legendary_buy_times = gathered
+        .legendary_item_buy_times
+        .into_iter()
+        .map(|nth| DisplayTimeDelta::from(median_td(&nth)).with_samples(&nth))
+        .collect();
+    GroupStats {
+        title: title.to_string(),
+        id: title.to_lowercase().replace(' ', ""),
+        wins: gathered.wins,
+        losses: gathered.losses,
+        games_played: gathered.wins + gathered.losses,
+        metrics: metric::aggregate_all(&gathered.games, &gathered.custom_metrics, role),
+        at_minute_stats,
+        previous_at_minute_stats: None,
+        heatmap_data,
+        trend_data,
+        legendary_buy_times,
+        per_role_per_champ: vec![],
+        per_role_per_enemy: vec![],
+        champion_pools: vec![],
+        duos: vec![],
+        goals: vec![],
+        games: vec![],
+        excluded: vec![],
+        baseline: None,
+        lp_delta: None,
+    }
+}
+
+fn matches_by_role_champ<'a>(
+    matches: impl IntoIterator<Item = &'a &'a json::Match>,
+    puuid: &'a str,
+) -> Vec<(Role, ChampMatches<'a>)> {
+    let mut map = HashMap::new();
+    for m in matches {
+        let player = get_player(m, puuid);
+        let role = player.team_position;
+        let champion = player.champion_name.clone();
+        map.entry(role)
+            .or_insert_with(HashMap::new)
+            .entry(champion)
+            .or_insert_with(Vec::new)
+            .push(*m);
+    }
+    map.into_iter()
+        .map(|(role, champ_map)| {
+            let champ_map = champ_map
+                .into_iter()
+                // This sorts by the number of matches played with the champion, highest first
+                .sorted_unstable_by_key(|(_, matches)| -(i32::try_from(matches.len()).unwrap()))
+                .collect::<Vec<_>>();
+            (role, champ_map)
+        })
+        // This sorts by the number of matches played in the role, highest first
+        .sorted_by_cached_key(|(_, champ_map)| {
+            -(i32::try_from(
+                champ_map
+                    .iter()
+                    .map(|(_, matches)| matches.len())
+                    .sum::<usize>(),
+            )
+            .unwrap())
+        })
+        .collect()
+}
+
+fn matches_by_role_enemy<'a>(
+    state: &State,
+    matches: impl IntoIterator<Item = &'a &'a json::Match>,
+    puuid: &'a str,
+) -> Vec<(Role, ChampMatches<'a>)> {
+    let mut map = HashMap::new();
+    for m in matches {
+        let player = get_player(m, puuid);
+        let timeline = state.timeline_per_match.get(&m.metadata.match_id);
+        // Records against a champion are only worth something if it really
+        // was the opponent
+        let Some(opponent) = get_opponent(m, timeline.as_deref(), player) else {
+            continue;
+        };
+        let role = player.team_position;
+        let enemy = opponent.champion_name.clone();
+        map.entry(role)
+            .or_insert_with(HashMap::new)
+            .entry(enemy)
+            .or_insert_with(Vec::new)
+            .push(*m);
+    }
+    map.into_iter()
+        .map(|(role, champ_map)| {
+            let champ_map = champ_map
+                .into_iter()
+                // This sorts by the number of matches played against the champion, highest first
+                .sorted_unstable_by_key(|(_, matches)| -(i32::try_from(matches.len()).unwrap()))
+                // Now sort stable by the winrate, stable so that the previous sort is not affected when the winrate is the same
+                .sorted_by_cached_key(|(_, matches)| {
+                    let wins = matches
+                        .iter()
+                        .filter(|m| {
+                            m.info
+                                .participants
+                                .iter()
+                                .any(|p| p.puuid == puuid && p.win)
+                        })
+                        .count();
+                    let wins = i32::try_from(wins).unwrap();
+                    let len = i32::try_from(matches.len()).unwrap();
+                    let winrate = 100.0 * f64::from(wins) / f64::from(len);
+                    OrderedFloat(winrate)
+                })
+                .collect::<Vec<_>>();
+            (role, champ_map)
+        })
+        // This sorts by the number of matches played in the role, highest first
+        .sorted_by_cached_key(|(_, champ_map)| {
+            -(i32::try_from(
+                champ_map
+                    .iter()
+                    .map(|(_, matches)| matches.len())
+                    .sum::<usize>(),
+            )
+            .unwrap())
+        })
+        .collect()
+}
+
+/// The start of the default window of matches that stats are calculated for.
+pub fn default_start() -> DateTime<Utc> {
+    Utc::now() - chrono::Duration::weeks(NUM_WEEKS)
+}
+
+fn filter_matches<'a>(
+    player_matches: &'a HashMap<String, Match>,
+    puuid: &str,
+    role: Option<Role>,
+    champion: Option<&str>,
+    period: &Period,
+    queue_id: Option<i32>,
+) -> Vec<&'a Match> {
+    player_matches
+        .values()
+        .filter(|m| {
+            let champ_match = champion.is_none_or(|champion| {
+                m.info.participants.iter().any(|p| {
+                    p.puuid == puuid && normalize_champion_name(&p.champion_name) == champion
+                })
+            });
+            let role_match = role.is_none_or(|role| {
+                m.info
+                    .participants
+                    .iter()
+                    .any(|p| p.puuid == puuid && p.team_position == role)
+            });
+            champ_match
+                && role_match
+                && queue_id.is_none_or(|queue_id| m.info.queue_id == queue_id)
+                && m.info.game_mode == "CLASSIC"
+                && m.info.game_duration > TimeDelta::minutes(5)
+                && period.contains(m)
+        })
+        .collect()
+}
+
+fn stats_for_matches(
+    state: &State,
+    matches: &[&Match],
+    puuid: &str,
+    role: Option<Role>,
+    champion: Option<&str>,
+    title: &str,
+) -> GroupStats {
+    let (matches, excluded) = exclusions::split_excluded(state, matches, puuid);
+    let matches = matches.as_slice();
+    let gathered = gather_stats(state, matches, puuid);
+    let mut display_stats = convert_stats(title, gathered, role);
+    display_stats.excluded = excluded;
+    if champion.is_none() {
+        display_stats.per_role_per_champ = per_role_per_champ(matches, puuid, role, state, title);
+        display_stats.champion_pools = pool::champion_pools(&display_stats.per_role_per_champ);
+    }
+    display_stats.per_role_per_enemy = per_role_per_enemy(matches, puuid, role, state, title);
+    display_stats.duos = duos::find_duos(state, matches, puuid, title);
+    display_stats.games = matches
+        .iter()
+        .map(|m| GameSummary::new(m, puuid))
+        // Newest first
+        .sorted_by_key(|game| std::cmp::Reverse(game.start))
+        .collect();
+    display_stats
+}
+
+pub async fn calc_stats(
+    state: State,
+    player: &mut Player,
+    role: Option<Role>,
+    champion: Option<&str>,
+) -> Result<Vec<GroupStats>> {
+    calc_stats_for_weeks(
+        state,
+        player,
+        role,
+        champion,
+        NUM_WEEKS,
+        &TagFilter::default(),
+    )
+    .await
+}
+
+/// Like `calc_stats`, but over the last `weeks` weeks instead of the default
+/// window, and only for the games that pass the tag filter.
+pub async fn calc_stats_for_weeks(
+    state: State,
+    player: &mut Player,
+    role: Option<Role>,
+    champion: Option<&str>,
+    weeks: i64,
+    tags: &TagFilter,
+) -> Result<Vec<GroupStats>> {
+    let from = Utc::now() - chrono::Duration::weeks(weeks);
+    debug!("Getting puuid");
+    let puuid = get_puuid_and_canonical_name(&state, player).await?;
+    debug!("Getting match history");
+    update_match_history(&state, player, from).await?;
+    debug!("Calculating baseline");
+    // Without a role there's nothing like-for-like to compare to
+    let baseline =
+        role.and_then(|role| baseline::Baseline::for_player(&state, &puuid, role, champion));
+    let snapshots = state
+        .rank_history
+        .get(&puuid)
+        .map(|history| history.snapshots)
+        .unwrap_or_default();
+    debug!("Calculating stats");
+    let now = Utc::now();
+    let player_matches = state.matches_per_puuid.get(&puuid).unwrap();
+    let mut all_matches = filter_matches(
+        &player_matches,
+        &puuid,
+        role,
+        champion,
+        &Period::since(from),
+        Some(SOLO_QUEUE_ID),
+    );
+    all_matches.retain(|m| tags.matches(&state, m, &puuid));
+    let mut group_stats = all_matches
+        .clone()
+        .into_iter()
+        .sorted_by_key(|m| m.info.game_start_timestamp)
+        .chunk_by(|m| (now - m.info.game_start_timestamp).num_weeks())
+        .into_iter()
+        .map(|(weeks_ago, matches)| {
+            let matches = matches.collect::<Vec<_>>();
+            let title = format!("Week {}", weeks - weeks_ago);
+            let mut group = stats_for_matches(&state, &matches, &puuid, role, champion, &title);
+            group.goals = goals::goal_progress(&state, player, &matches, &puuid);
+            if let Some(baseline) = &baseline {
+                baseline.rank(&mut group);
+            }
+            group.lp_delta = rank::lp_delta(
+                &snapshots,
+                now - chrono::Duration::weeks(weeks_ago + 1),
+                now - chrono::Duration::weeks(weeks_ago),
+            );
+            group
+        })
+        .collect::<Vec<_>>();
+    let mut total = stats_for_matches(&state, &all_matches, &puuid, role, champion, "Total");
+    total.goals = goals::goal_progress(&state, player, &all_matches, &puuid);
+    if let Some(baseline) = &baseline {
+        baseline.rank(&mut total);
+    }
+    total.lp_delta = rank::lp_delta(&snapshots, from, now);
+    group_stats.push(total);
+    Ok(group_stats)
+}
+
+/// Compares every week to the one before it, skipping the total.
+pub fn compare_weeks(groups: &mut [GroupStats]) {
+    let mut previous_group: Option<GroupStats> = None;
+    for current_group in groups {
+        if current_group.title == "Total" {
+            continue;
+        }
+        if let Some(previous_week) = previous_group {
+            current_group.compare_to(&previous_week);
+            current_group.previous_at_minute_stats = Some(previous_week.at_minute_stats.clone());
+        }
+        previous_group = Some(current_group.clone());
+    }
+}
+
+/// Calculates stats for all of a player's matches in `period`, as a single
+/// group titled "Total". Returns `None` if there are no matches in the period.
+pub async fn calc_period_stats(
+    state: State,
+    player: &mut Player,
+    period: &Period,
+    role: Option<Role>,
+    champion: Option<&str>,
+) -> Result<Option<GroupStats>> {
+    let puuid = get_puuid_and_canonical_name(&state, player).await?;
+    update_match_history(&state, player, period.start()).await?;
+    let player_matches = state.matches_per_puuid.get(&puuid).unwrap();
+    let matches = filter_matches(
+        &player_matches,
+        &puuid,
+        role,
+        champion,
+        period,
+        Some(SOLO_QUEUE_ID),
+    );
+    if matches.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(stats_for_matches(
+        &state, &matches, &puuid, role, champion, "Total",
+    )))
+}
+
+/// Lists every game in the default window of matches that passes the filters,
+/// newest first.
+pub async fn calc_history(
+    state: State,
+    player: &mut Player,
+    role: Option<Role>,
+    champion: Option<&str>,
+    filter: &HistoryFilter,
+) -> Result<Vec<HistoryRow>> {
+    let from = default_start();
+    let puuid = get_puuid_and_canonical_name(&state, player).await?;
+    update_match_history(&state, player, from).await?;
+    let player_matches = state.matches_per_puuid.get(&puuid).unwrap();
+    let matches = filter_matches(
+        &player_matches,
+        &puuid,
+        role,
+        champion,
+        &Period::since(from),
+        Some(SOLO_QUEUE_ID),
+    );
+    Ok(history::history_rows(&state, &matches, &puuid, filter))
+}
+
+/// Bans and lane opponents over the default window of matches.
+pub async fn calc_bans(
+    state: State,
+    player: &mut Player,
+    role: Option<Role>,
+    champion: Option<&str>,
+) -> Result<BanAnalysis> {
+    let from = default_start();
+    let puuid = get_puuid_and_canonical_name(&state, player).await?;
+    update_match_history(&state, player, from).await?;
+    let player_matches = state.matches_per_puuid.get(&puuid).unwrap();
+    let matches = filter_matches(
+        &player_matches,
+        &puuid,
+        role,
+        champion,
+        &Period::since(from),
+        Some(SOLO_QUEUE_ID),
+    );
+    Ok(bans::ban_analysis(&state, &matches, &puuid))
+}
+
+/// The metrics for every game in the default window of matches, newest first.
+pub async fn calc_game_metrics(
+    state: State,
+    player: &mut Player,
+    role: Option<Role>,
+    champion: Option<&str>,
+) -> Result<Vec<GameMetrics>> {
+    let from = default_start();
+    let puuid = get_puuid_and_canonical_name(&state, player).await?;
+    update_match_history(&state, player, from).await?;
+    let player_matches = state.matches_per_puuid.get(&puuid).unwrap();
+    let matches = filter_matches(
+        &player_matches,
+        &puuid,
+        role,
+        champion,
+        &Period::since(from),
+        Some(SOLO_QUEUE_ID),
+    );
+    Ok(matches
+        .iter()
+        .sorted_by_key(|m| std::cmp::Reverse(m.info.game_start_timestamp))
+        .map(|m| {
+            let timeline = state.timeline_per_match.get(&m.metadata.match_id).unwrap();
+            metrics::game_metrics(m, &timeline, &puuid)
+        })
+        .collect())
+}
+
+/// One participant of a set of scrim games, with their stats over just those
+/// games.
+pub struct ScrimPlayer {
+    pub player: Player,
+    pub stats: GroupStats,
+}
+
+/// Fetches the given games, plus the games played with `tournament_code`,
+/// sorted by start time.
+async fn scrim_matches(
+    state: &State,
+    region: LeagueRegion,
+    match_ids: &[String],
+    tournament_code: Option<&str>,
+) -> Result<Vec<Match>> {
+    // Only used for rate limit status updates, and nobody is waiting for
+    // those here.
+    let placeholder = Player {
+        region,
+        game_name: String::new(),
+        tag_line: String::new(),
+    };
+    let mut match_ids = match_ids.to_vec();
+    if let Some(tournament_code) = tournament_code {
+        if !state.offline {
+            match_ids.extend(
+                get_match_ids_by_tournament_code(
+                    state,
+                    region.into(),
+                    tournament_code,
+                    &placeholder,
+                )
+                .await?,
+            );
+        }
+        // Imported games have the code too
+        match_ids.extend(
+            state
+                .matches_per_puuid
+                .iter()
+                .flat_map(|matches| {
+                    matches
+                        .values()
+                        .filter(|m| m.info.tournament_code.as_deref() == Some(tournament_code))
+                        .map(|m| m.metadata.match_id.clone())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>(),
+        );
+    }
+    let match_ids = match_ids.into_iter().unique().collect::<Vec<_>>();
+    if match_ids.is_empty() {
+        return Err("No games found".into());
+    }
+    let mut matches = fetch_matches_by_id(state, region.into(), &match_ids, &placeholder).await?;
+    matches.sort_by_key(|m| m.info.game_start_timestamp);
+    Ok(matches)
+}
+
+/// Calculates stats for everyone who played in the given games, plus the
+/// games played with `tournament_code`, instead of going by match history.
+/// Players are ordered by team and role of the first game.
+pub async fn calc_scrim(
+    state: State,
+    region: LeagueRegion,
+    match_ids: &[String],
+    tournament_code: Option<&str>,
+    role: Option<Role>,
+    champion: Option<&str>,
+) -> Result<Vec<ScrimPlayer>> {
+    let matches = scrim_matches(&state, region, match_ids, tournament_code).await?;
+    let first_game = &matches[0];
+    let puuids = matches
+        .iter()
+        .flat_map(|m| &m.info.participants)
+        .map(|p| p.puuid.clone())
+        .unique()
+        .sorted_by_key(|puuid| {
+            // Subs that weren't in the first game go last
+            first_game
+                .info
+                .participants
+                .iter()
+                .find(|p| p.puuid == *puuid)
+                .map_or((1, 0, Role::None), |p| (0, p.team_id, p.team_position))
+        })
+        .collect::<Vec<_>>();
+    let mut players = vec![];
+    for puuid in puuids {
+        let player_matches = matches
+            .iter()
+            .filter(|m| m.info.participants.iter().any(|p| p.puuid == puuid))
+            .map(|m| (m.metadata.match_id.clone(), m.clone()))
+            .collect::<HashMap<_, _>>();
+        let filtered = filter_matches(
+            &player_matches,
+            &puuid,
+            role,
+            champion,
+            &Period::default(),
+            None,
+        );
+        if filtered.is_empty() {
+            continue;
+        }
+        let participant = filtered
+            .iter()
+            .max_by_key(|m| m.info.game_start_timestamp)
+            .map(|m| get_player(m, &puuid))
+            .unwrap();
+        players.push(ScrimPlayer {
+            player: Player {
+                region,
+                game_name: participant.riot_id_game_name.clone(),
+                tag_line: participant.riot_id_tagline.clone(),
+            },
+            stats: stats_for_matches(&state, &filtered, &puuid, role, champion, "Total"),
+        });
+    }
+    Ok(players)
+}
+
+/// Compares the two teams that played the given games, plus the games
+/// played with `tournament_code`.
+pub async fn calc_team_report(
+    state: State,
+    region: LeagueRegion,
+    match_ids: &[String],
+    tournament_code: Option<&str>,
+) -> Result<TeamReport> {
+    let matches = scrim_matches(&state, region, match_ids, tournament_code).await?;
+    Ok(team_report::team_report(&state, &matches))
+}
+
+/// Calculates how the players do when they play together, over the default
+/// window of matches.
+pub async fn calc_roster_aggregates(
+    state: State,
+    players: &mut [Player],
+) -> Result<RosterAggregates> {
+    let from = default_start();
+    let mut puuids = vec![];
+    for player in players.iter_mut() {
+        puuids.push(get_puuid_and_canonical_name(&state, player).await?);
+        update_match_history(&state, player, from).await?;
+    }
+    let period = Period::since(from);
+    let per_member = puuids
+        .iter()
+        .map(|puuid| state.matches_per_puuid.get(puuid).unwrap())
+        .collect::<Vec<_>>();
+    let matches = per_member
+        .iter()
+        .zip(&puuids)
+        .flat_map(|(player_matches, puuid)| {
+            filter_matches(
+                player_matches,
+                puuid,
+                None,
+                None,
+                &period,
+                Some(SOLO_QUEUE_ID),
+            )
+        });
+    let puuid_set = puuids.iter().map(String::as_str).collect();
+    Ok(roster::roster_aggregates(matches, &puuid_set))
+}
+
+fn per_role_per_enemy<'a>(
+    matches: impl IntoIterator<Item = &'a &'a json::Match>,
+    puuid: &'a str,
+    role: Option<Role>,
+    state: &State,
+    title: &str,
+) -> Vec<(Role, DisplayChampMatches)> {
+    matches_by_role_enemy(state, matches, puuid)
+        .into_iter()
+        .filter_map(|(by_role, champ_map)| {
+            if role.is_some() && role != Some(by_role) {
+                return None;
+            }
+            Some((
+                by_role,
+                champ_map
+                    .into_iter()
+                    .map(|(enemy, enemy_matches)| {
+                        let normalized_enemy = normalize_champion_name(&enemy);
+                        let role_enemy_stats = gather_stats(state, &enemy_matches, puuid);
+                        let role_enemy_display_stats =
+                            convert_stats(title, role_enemy_stats, Some(by_role));
+                        (enemy, normalized_enemy, role_enemy_display_stats)
+                    })
+                    .collect(),
+            ))
+        })
+        .collect()
+}
+
+fn per_role_per_champ(
+    matches: &[&Match],
+    puuid: &str,
+    role: Option<Role>,
+    state: &State,
+    title: &str,
+) -> Vec<(Role, DisplayChampMatches)> {
+    matches_by_role_champ(matches, puuid)
+        .into_iter()
+        .filter_map(|(by_role, champ_map)| {
+            if role.is_some() && role != Some(by_role) {
+                return None;
+            }
+            Some((
+                by_role,
+                champ_map
+                    .into_iter()
+                    .map(|(champ, champ_matches)| {
+                        let normalized_champ = normalize_champion_name(&champ);
+                        let role_champ_stats = gather_stats(state, &champ_matches, puuid);
+                        let role_champ_display_stats =
+                            convert_stats(title, role_champ_stats, Some(by_role));
+                        (champ, normalized_champ, role_champ_display_stats)
+                    })
+                    .collect(),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::level_for_xp;
+    use crate::riot_api::json;
+    use chrono::TimeDelta;
+    use std::collections::HashMap;
+    use test_case::test_case;
+
+    #[test_case(0, 1.0)]
+    #[test_case(280, 2.0)]
+    #[test_case(660, 3.0)]
+    #[test_case(1140, 4.0)]
+    #[test_case(1720, 5.0)]
+    #[test_case(2400, 6.0)]
+    #[test_case(3180, 7.0)]
+    #[test_case(4060, 8.0)]
+    #[test_case(5040, 9.0)]
+    #[test_case(6120, 10.0)]
+    #[test_case(7300, 11.0)]
+    #[test_case(8580, 12.0)]
+    #[test_case(9960, 13.0)]
+    #[test_case(11440, 14.0)]
+    #[test_case(13020, 15.0)]
+    #[test_case(14700, 16.0)]
+    #[test_case(16480, 17.0)]
+    #[test_case(18360, 18.0)]
+    #[test_case(140, 1.5)]
+    #[test_case(900, 3.5)]
+    #[test_case(999_999, 18.0)]
+    fn test_level_for_xp(xp: i32, level: f64) {
+        assert!((level_for_xp(xp) - level).abs() < 0.01);
+    }
+
+    #[test_case(&[1_u32, 2, 3, 4, 5], 3.0)]
+    #[test_case(&[1_i32, 2, 3, 4, 5], 3.0)]
+    #[test_case(&[1.0, 2.0, 3.0, 4.0, 5.0], 3.0)]
+    #[test_case(&[5_u32, 4, 1, 3, 2], 3.0)]
+    #[test_case(&[0, u32::MAX], 2_147_483_647.5)]
+    #[test_case(&[0, u32::MAX, u32::MAX], 2_863_311_530.0)]
+    #[test_case(&[u32::MAX, 0, u32::MAX], 2_863_311_530.0)]
+    #[test_case(&[0.0, 1.0, 2.0, 3.0, 4.0], 2.0)]
+    fn test_average<'a, T>(values: impl IntoIterator<Item = &'a T>, expected: f64)
+    where
+        T: Copy + Into<f64> + 'a,
+    {
+        let average = super::average(values);
+        assert!((average - expected).abs() < 0.01);
+    }
+
+    //    #[test_case(&[1.0_f64, 2.0, 3.0, 4.0, 5.0], 3.0)]
+    //    #[test_case(&[0.0, f64::MAX], f64::MAX / 2.0)]
+    //    #[test_case(&[0.0, f64::MAX, f64::MAX], f64::MAX)]
+    //    #[test_case(&[0, 0, 0, 0, 0, 1], 0.0)]
+    //    #[test_case(&[0, 0, 0, 0, 0, 0, 1], 0.0)]
+    //fn test_median<'a, T>(values: impl IntoIterator<Item = impl Borrow<T>>, expected: f64)
+    //where
+    //T: Clone + Into<f64> + 'a,
+    #[test]
+    fn test_median() {
+        let values: [f64; 5] = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let median = super::median(&values);
+        assert!((median - 3.0).abs() < 0.01);
+    }
+
+    #[test_case("14.1", Some("2024-01-01") ; "first patch of the year")]
+    #[test_case("14.20", Some("2024-09-23") ; "released 2024-10-09")]
+    #[test_case("15.3", Some("2025-01-29") ; "released 2025-02-05")]
+    #[test_case("14.0", None ; "no patch zero")]
+    #[test_case("latest", None ; "not a patch")]
+    fn test_patch_released_after(patch: &str, expected: Option<&str>) {
+        assert_eq!(
+            super::patch_released_after(patch).map(|time| time.date_naive().to_string()),
+            expected.map(ToString::to_string)
+        );
+    }
+
+    #[test]
+    fn test_median_without_values() {
+        assert!(super::median::<f64>(&[]).is_nan());
+    }
+
+    #[test]
+    fn test_convert_stats_without_games() {
+        let stats = super::convert_stats("Total", super::WeekStatsGathering::default(), None);
+        assert_eq!(stats.games_played, 0);
+    }
+
+    #[test]
+    fn test_buys_sells() {
+        let frames = [
+            json::Frame {
+                events: vec![],
+                participant_frames: HashMap::new(),
+                timestamp: TimeDelta::minutes(0),
+            },
+            json::Frame {
+                events: vec![],
+                participant_frames: HashMap::new(),
+                timestamp: TimeDelta::minutes(1),
+            },
+            json::Frame {
+                events: vec![
+                    json::Event::ItemPurchased {
+                        item_id: 2503,
+                        participant_id: 1,
+                        timestamp: TimeDelta::minutes(1) + TimeDelta::seconds(30),
+                    },
+                    json::Event::ItemPurchased {
+                        item_id: 2503,
+                        participant_id: 2,
+                        timestamp: TimeDelta::minutes(1) + TimeDelta::seconds(45),
+                    },
+                ],
+                participant_frames: HashMap::new(),
+                timestamp: TimeDelta::minutes(2),
+            },
+            json::Frame {
+                events: vec![
+                    json::Event::ItemSold {
+                        item_id: 2503,
+                        participant_id: 1,
+                        timestamp: TimeDelta::minutes(2) + TimeDelta::seconds(25),
+                    },
+                    json::Event::ItemPurchased {
+                        item_id: 3118,
+                        participant_id: 1,
+                        timestamp: TimeDelta::minutes(2) + TimeDelta::seconds(30),
+                    },
+                ],
+                participant_frames: HashMap::new(),
+                timestamp: TimeDelta::minutes(3),
+            },
+            json::Frame {
+                events: vec![
+                    json::Event::ItemPurchased {
+                        item_id: 2503,
+                        participant_id: 2,
+                        timestamp: TimeDelta::minutes(3) + TimeDelta::seconds(30),
+                    },
+                    json::Event::ItemUndo {
+                        before_id: 2503,
+                        after_id: 0,
+                        gold_gain: 1800,
+                        participant_id: 2,
+                        timestamp: TimeDelta::minutes(3) + TimeDelta::seconds(31),
+                    },
+                    json::Event::ItemPurchased {
+                        item_id: 2504,
+                        participant_id: 2,
+                        timestamp: TimeDelta::minutes(3) + TimeDelta::seconds(32),
+                    },
+                    json::Event::ItemPurchased {
+                        item_id: 3116,
+                        participant_id: 1,
+                        timestamp: TimeDelta::minutes(3) + TimeDelta::seconds(45),
+                    },
+                ],
+                participant_frames: HashMap::new(),
+                timestamp: TimeDelta::minutes(4),
+            },
+        ];
+        let expected_player1 = vec![
+            TimeDelta::minutes(2) + TimeDelta::seconds(30),
+            TimeDelta::minutes(3) + TimeDelta::seconds(45),
+        ];
+        let expected_player2 = vec![
+            TimeDelta::minutes(1) + TimeDelta::seconds(45),
+            TimeDelta::minutes(3) + TimeDelta::seconds(32),
+        ];
+        let player1_buys = super::get_legendary_buys(&frames, 1);
+        let player2_buys = super::get_legendary_buys(&frames, 2);
+        assert_eq!(player1_buys, expected_player1);
+        assert_eq!(player2_buys, expected_player2);
+    }
+
+    #[test_case(1001, json::ItemType::Boots)]
+    #[test_case(1011, json::ItemType::Epic)]
+    #[test_case(1026, json::ItemType::Basic)]
+    #[test_case(3040, json::ItemType::Legendary)]
+    #[test_case(3041, json::ItemType::Legendary)]
+    #[test_case(3046, json::ItemType::Legendary)]
+    #[test_case(2055, json::ItemType::Consumable)]
+    #[test_case(2003, json::ItemType::Potion)]
+    #[test_case(3340, json::ItemType::Trinket)]
+    #[test_case(1054, json::ItemType::Starter)]
+    fn test_ranks_map(item_id: i32, expected: json::ItemType) {
+        assert_eq!(super::ITEM_RANKS.get(&item_id), Some(&expected));
+    }
+}
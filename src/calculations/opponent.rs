@@ -0,0 +1,407 @@
+use super::timeline_get_player_id;
+use crate::{
+    from_str_value,
+    riot_api::json::{self, Role},
+};
+use itertools::Itertools;
+use std::fmt::{self, Display, Formatter};
+
+const SMITE: i32 = 11;
+// Below this, like for a guess, the opponent is too likely to be wrong to
+// compare the player to.
+const MIN_CONFIDENCE: f64 = 0.3;
+// Laning, before people start roaming and grouping.
+const LANE_MINUTES: std::ops::RangeInclusive<i64> = 2..=10;
+// Someone in the same lane for less than this share of the laning minutes
+// wasn't really laning against the player.
+const MIN_LANE_SHARE: f64 = 0.5;
+// The map is about 15000 units on each side. Lanes run along the edges and
+// the diagonal, the bases are in the corners.
+const LANE_WIDTH: i32 = 2500;
+const MAP_SIZE: i32 = 15000;
+const BASE_SIZE: i32 = 4000;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum MapRegion {
+    Base,
+    Top,
+    Middle,
+    Bottom,
+    Jungle,
+}
+
+fn map_region(point: json::Point) -> MapRegion {
+    let json::Point { x, y } = point;
+    if x + y < BASE_SIZE || x + y > 2 * MAP_SIZE - BASE_SIZE {
+        MapRegion::Base
+    } else if x < LANE_WIDTH || y > MAP_SIZE - LANE_WIDTH {
+        MapRegion::Top
+    } else if y < LANE_WIDTH || x > MAP_SIZE - LANE_WIDTH {
+        MapRegion::Bottom
+    } else if (x - y).abs() < LANE_WIDTH {
+        MapRegion::Middle
+    } else {
+        MapRegion::Jungle
+    }
+}
+
+/// How the lane opponent was found, from most to least reliable.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OpponentSource {
+    /// The other jungler, by Smite
+    Smite,
+    /// Who was in the same lane during laning
+    LanePositions,
+    /// Riot's `teamPosition`
+    TeamPosition,
+    /// Riot's `individualPosition`
+    IndividualPosition,
+    /// Nothing to go on, so any enemy
+    Guess,
+}
+
+impl Display for OpponentSource {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            OpponentSource::Smite => write!(f, "both having Smite"),
+            OpponentSource::LanePositions => write!(f, "laning in the same lane"),
+            OpponentSource::TeamPosition => write!(f, "the assigned positions"),
+            OpponentSource::IndividualPosition => write!(f, "the played positions"),
+            OpponentSource::Guess => write!(f, "a guess"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Opponent<'a> {
+    pub participant: &'a json::Participant,
+    pub source: OpponentSource,
+    /// From 0 for a guess to 1 when several signs agree
+    pub confidence: f64,
+}
+
+impl Opponent<'_> {
+    /// Whether we're sure enough of the opponent for stats against them,
+    /// like the gold and CS differences.
+    pub fn is_reliable(&self) -> bool {
+        self.confidence >= MIN_CONFIDENCE
+    }
+}
+
+fn has_smite(participant: &json::Participant) -> bool {
+    participant.summoner1_id == SMITE || participant.summoner2_id == SMITE
+}
+
+/// The only item that passes `filter`, if exactly one does.
+fn only<'a>(
+    participants: &[&'a json::Participant],
+    filter: impl Fn(&json::Participant) -> bool,
+) -> Option<&'a json::Participant> {
+    participants
+        .iter()
+        .filter(|p| filter(p))
+        .exactly_one()
+        .ok()
+        .copied()
+}
+
+/// Where someone was in each of the laning minutes, leaving out the base.
+fn lane_regions(timeline: &json::Timeline, puuid: &str) -> Vec<(i64, MapRegion)> {
+    let id = timeline_get_player_id(timeline, puuid);
+    timeline
+        .info
+        .frames
+        .iter()
+        .filter(|frame| LANE_MINUTES.contains(&frame.timestamp.num_minutes()))
+        .filter_map(|frame| {
+            let region = map_region(frame.participant_frames.get(&id)?.position);
+            (region != MapRegion::Base).then_some((frame.timestamp.num_minutes(), region))
+        })
+        .collect()
+}
+
+/// The share of the player's laning minutes in which the other was in the
+/// same lane.
+#[allow(clippy::cast_precision_loss)]
+fn lane_share(player: &[(i64, MapRegion)], other: &[(i64, MapRegion)], lane: MapRegion) -> f64 {
+    let minutes = player.iter().filter(|(_, region)| *region == lane).count();
+    if minutes == 0 {
+        return 0.0;
+    }
+    let shared = player
+        .iter()
+        .filter(|(minute, region)| *region == lane && other.contains(&(*minute, lane)))
+        .count();
+    shared as f64 / minutes as f64
+}
+
+/// The enemy who laned against the player, by where they were during
+/// laning. With two enemies in the lane, like in bottom lane, the one with
+/// the closest CS is the opponent.
+fn lane_opponent<'a>(
+    timeline: &json::Timeline,
+    player: &json::Participant,
+    enemies: &[&'a json::Participant],
+) -> Option<(&'a json::Participant, f64)> {
+    let player_regions = lane_regions(timeline, &player.puuid);
+    let lane = player_regions
+        .iter()
+        .map(|(_, region)| *region)
+        .counts()
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(region, _)| region)
+        .filter(|region| *region != MapRegion::Jungle)?;
+    let candidates = enemies
+        .iter()
+        .map(|enemy| {
+            let share = lane_share(&player_regions, &lane_regions(timeline, &enemy.puuid), lane);
+            (*enemy, share)
+        })
+        .filter(|(_, share)| *share >= MIN_LANE_SHARE)
+        .collect::<Vec<_>>();
+    match candidates.as_slice() {
+        [] => None,
+        [single] => Some(*single),
+        _ => {
+            let cs = |participant: &json::Participant| participant.total_minions_killed;
+            let &(enemy, share) = candidates
+                .iter()
+                .min_by_key(|(enemy, _)| (cs(enemy) - cs(player)).abs())?;
+            // Less sure, since it's not only about where they were
+            Some((enemy, share * 0.75))
+        }
+    }
+}
+
+/// Finds the player's lane opponent. Riot's `teamPosition` is sometimes
+/// wrong or missing, so where people were during laning and who has Smite
+/// count for more, and the positions only decide when those don't.
+pub fn infer_opponent<'a>(
+    m: &'a json::Match,
+    timeline: Option<&json::Timeline>,
+    player: &'a json::Participant,
+) -> Opponent<'a> {
+    let enemies = m
+        .info
+        .participants
+        .iter()
+        .filter(|p| p.team_id != player.team_id)
+        .collect::<Vec<_>>();
+    let by_team_position = (player.team_position != Role::None)
+        .then(|| only(&enemies, |p| p.team_position == player.team_position))
+        .flatten();
+    let opponent = |participant: &'a json::Participant, source, confidence: f64| {
+        // Two independent signs pointing at the same enemy
+        let agrees = source != OpponentSource::TeamPosition
+            && by_team_position.is_some_and(|p| p.puuid == participant.puuid);
+        Opponent {
+            participant,
+            source,
+            confidence: if agrees { 1.0 } else { confidence },
+        }
+    };
+
+    if let Some(enemy) = only(&enemies, has_smite).filter(|_| has_smite(player)) {
+        return opponent(enemy, OpponentSource::Smite, 0.9);
+    }
+    if let Some((enemy, share)) =
+        timeline.and_then(|timeline| lane_opponent(timeline, player, &enemies))
+    {
+        return opponent(enemy, OpponentSource::LanePositions, share);
+    }
+    if let Some(enemy) = by_team_position {
+        return opponent(enemy, OpponentSource::TeamPosition, 0.7);
+    }
+    // "Invalid" when Riot can't tell
+    let individual_position = |p: &json::Participant| {
+        from_str_value::<Role>(&p.individual_position)
+            .ok()
+            .filter(|role| *role != Role::None)
+    };
+    if let Some(enemy) = individual_position(player)
+        .and_then(|position| only(&enemies, |p| individual_position(p) == Some(position)))
+    {
+        return opponent(enemy, OpponentSource::IndividualPosition, 0.5);
+    }
+    opponent(enemies[0], OpponentSource::Guess, 0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{infer_opponent, lane_share, map_region, MapRegion, OpponentSource, SMITE};
+    use crate::{
+        from_str_value,
+        riot_api::json::{self, Point},
+    };
+    use chrono::TimeDelta;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use test_case::test_case;
+
+    const POSITIONS: [&str; 5] = ["TOP", "JUNGLE", "MIDDLE", "BOTTOM", "UTILITY"];
+
+    /// Participants 1 to 5 are on the blue team and 6 to 10 on the red team,
+    /// each in the `POSITIONS` order unless `positions` says otherwise.
+    fn game(positions: &[(i32, &str)], smite: &[i32]) -> json::Match {
+        let participants = (1..=10)
+            .map(|id| {
+                let position = positions.iter().find(|(other, _)| *other == id).map_or(
+                    POSITIONS[usize::try_from((id - 1) % 5).unwrap()],
+                    |(_, position)| *position,
+                );
+                let individual_position = if position.is_empty() {
+                    "Invalid"
+                } else {
+                    position
+                };
+                json::Participant {
+                    champion_name: format!("Champion{id}"),
+                    individual_position: individual_position.to_string(),
+                    id,
+                    puuid: format!("player{id}"),
+                    summoner1_id: 4,
+                    summoner2_id: if smite.contains(&id) { SMITE } else { 14 },
+                    team_id: if id <= 5 { 100 } else { 200 },
+                    team_position: from_str_value(position).unwrap(),
+                    ..json::Participant::default()
+                }
+            })
+            .collect::<Vec<_>>();
+        serde_json::from_value(json!({
+            "metadata": { "dataVersion": "2", "matchId": "EUW1_1", "participants": [] },
+            "info": {
+                "gameCreation": 0,
+                "gameDuration": 1800,
+                "gameId": 1,
+                "gameMode": "CLASSIC",
+                "gameName": "",
+                "gameStartTimestamp": 0,
+                "gameType": "MATCHED_GAME",
+                "gameVersion": "14.20.1",
+                "mapId": 11,
+                "participants": participants,
+                "platformId": "EUW1",
+                "queueId": 420,
+                "teams": [],
+            },
+        }))
+        .unwrap()
+    }
+
+    /// Every participant stays at the same spot for the whole laning phase.
+    fn timeline(spots: &[(usize, Point)]) -> json::Timeline {
+        let frames = (0..=10)
+            .map(|minute| json::Frame {
+                events: vec![],
+                participant_frames: spots
+                    .iter()
+                    .map(|&(id, position)| {
+                        let frame = json::ParticipantFrame {
+                            total_gold: 0,
+                            current_gold: 0,
+                            minions_killed: 0,
+                            jungle_minions_killed: 0,
+                            position,
+                            xp: 0,
+                        };
+                        (id, frame)
+                    })
+                    .collect::<HashMap<_, _>>(),
+                timestamp: TimeDelta::minutes(minute),
+            })
+            .collect();
+        json::Timeline {
+            metadata: json::Metadata {
+                data_version: "2".to_string(),
+                match_id: "EUW1_1".to_string(),
+                participants: vec![],
+            },
+            info: json::TimelineInfo {
+                frames,
+                participants: (1..=10)
+                    .map(|id| json::TimelineParticipant {
+                        participant_id: id,
+                        puuid: format!("player{id}"),
+                    })
+                    .collect(),
+            },
+        }
+    }
+
+    const TOP_LANE: Point = Point { x: 1000, y: 10000 };
+    const MID_LANE: Point = Point { x: 7500, y: 7000 };
+    const JUNGLE: Point = Point { x: 4000, y: 8000 };
+
+    #[test_case(500, 500, MapRegion::Base)]
+    #[test_case(14500, 14500, MapRegion::Base)]
+    #[test_case(1000, 10000, MapRegion::Top)]
+    #[test_case(6000, 14000, MapRegion::Top)]
+    #[test_case(10000, 1000, MapRegion::Bottom)]
+    #[test_case(14000, 6000, MapRegion::Bottom)]
+    #[test_case(7500, 7000, MapRegion::Middle)]
+    #[test_case(4000, 8000, MapRegion::Jungle)]
+    fn test_map_region(x: i32, y: i32, expected: MapRegion) {
+        assert_eq!(map_region(Point { x, y }), expected);
+    }
+
+    #[test]
+    fn test_lane_share() {
+        let player = [
+            (2, MapRegion::Top),
+            (3, MapRegion::Top),
+            (4, MapRegion::Top),
+            (5, MapRegion::Jungle),
+        ];
+        let laner = [
+            (2, MapRegion::Top),
+            (3, MapRegion::Top),
+            (5, MapRegion::Top),
+        ];
+        let roamer = [(4, MapRegion::Top), (5, MapRegion::Middle)];
+        assert!((lane_share(&player, &laner, MapRegion::Top) - 2.0 / 3.0).abs() < 1e-9);
+        assert!((lane_share(&player, &roamer, MapRegion::Top) - 1.0 / 3.0).abs() < 1e-9);
+        assert!(lane_share(&player, &laner, MapRegion::Bottom).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_infer_opponent_by_team_position() {
+        let m = game(&[], &[]);
+        let opponent = infer_opponent(&m, None, &m.info.participants[2]);
+        assert_eq!(opponent.participant.puuid, "player8");
+        assert_eq!(opponent.source, OpponentSource::TeamPosition);
+        assert!(opponent.is_reliable());
+    }
+
+    #[test]
+    fn test_infer_opponent_by_smite_over_team_position() {
+        // Riot has the junglers swapped with the mid laners
+        let m = game(&[(7, "MIDDLE"), (8, "JUNGLE")], &[2, 7]);
+        let opponent = infer_opponent(&m, None, &m.info.participants[1]);
+        assert_eq!(opponent.participant.puuid, "player7");
+        assert_eq!(opponent.source, OpponentSource::Smite);
+    }
+
+    #[test]
+    fn test_infer_opponent_by_lane_positions() {
+        // The enemy top laner has no position, and their jungler is in top
+        // lane according to Riot
+        let m = game(&[(6, ""), (7, "TOP")], &[]);
+        let timeline = timeline(&[(1, TOP_LANE), (6, TOP_LANE), (7, JUNGLE), (8, MID_LANE)]);
+        let opponent = infer_opponent(&m, Some(&timeline), &m.info.participants[0]);
+        assert_eq!(opponent.participant.puuid, "player6");
+        assert_eq!(opponent.source, OpponentSource::LanePositions);
+        assert!(opponent.is_reliable());
+    }
+
+    #[test]
+    fn test_infer_opponent_guess_is_not_reliable() {
+        let m = game(
+            &[(3, ""), (6, ""), (7, ""), (8, ""), (9, ""), (10, "")],
+            &[],
+        );
+        let opponent = infer_opponent(&m, None, &m.info.participants[2]);
+        assert_eq!(opponent.source, OpponentSource::Guess);
+        assert!(!opponent.is_reliable());
+    }
+}
@@ -0,0 +1,143 @@
+use super::{percentage, DisplayChampMatches};
+use crate::riot_api::json::Role;
+use itertools::Itertools;
+use ordered_float::OrderedFloat;
+
+// Fewer games than this on a champion is too few to judge it.
+const MIN_EVALUATION_GAMES: u32 = 5;
+// How many games' worth of the role's average winrate gets mixed into each
+// champion's winrate, so a 3-0 doesn't beat a 30-20.
+const PRIOR_GAMES: f64 = 10.0;
+const TOP_CHAMPIONS: usize = 3;
+// More than this share of the role's games on one champion is a one-trick.
+const ONE_TRICK_SHARE: f64 = 70.0;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PoolChampion {
+    pub name: String,
+    pub id: String,
+    pub games: u32,
+    pub wins: u32,
+    pub winrate: f64,
+    /// Share of the role's games
+    pub share: f64,
+    /// Winrate pulled towards the role's average, less so the more games
+    /// there are
+    pub comfort: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct RolePool {
+    pub role: Role,
+    pub games: u32,
+    pub pool_size: usize,
+    /// Share of the role's games on the most played champions
+    pub top_share: f64,
+    pub one_trick: Option<String>,
+    /// Champions with enough games to judge, most comfortable first
+    pub comfort_picks: Vec<PoolChampion>,
+    /// Champions played too rarely to judge
+    pub too_rare: Vec<PoolChampion>,
+}
+
+fn role_pool(role: Role, champions: &[(&str, &str, u32, u32)]) -> RolePool {
+    let games = champions.iter().map(|(_, _, games, _)| games).sum::<u32>();
+    let wins = champions.iter().map(|(_, _, _, wins)| wins).sum::<u32>();
+    let role_winrate = percentage(wins, games);
+    let (comfort_picks, too_rare): (Vec<_>, Vec<_>) = champions
+        .iter()
+        .map(|&(name, id, champion_games, champion_wins)| PoolChampion {
+            name: name.to_string(),
+            id: id.to_string(),
+            games: champion_games,
+            wins: champion_wins,
+            winrate: percentage(champion_wins, champion_games),
+            share: percentage(champion_games, games),
+            comfort: (f64::from(champion_wins) * 100.0 + PRIOR_GAMES * role_winrate)
+                / (f64::from(champion_games) + PRIOR_GAMES),
+        })
+        .sorted_by_key(|champion| std::cmp::Reverse(OrderedFloat(champion.comfort)))
+        .partition(|champion| champion.games >= MIN_EVALUATION_GAMES);
+    let top_games = champions
+        .iter()
+        .map(|(_, _, games, _)| *games)
+        .sorted_unstable_by(|a, b| b.cmp(a))
+        .take(TOP_CHAMPIONS)
+        .sum::<u32>();
+    let one_trick = champions
+        .iter()
+        .find(|(_, _, champion_games, _)| percentage(*champion_games, games) > ONE_TRICK_SHARE)
+        .map(|(name, ..)| (*name).to_string());
+    RolePool {
+        role,
+        games,
+        pool_size: champions.len(),
+        top_share: percentage(top_games, games),
+        one_trick,
+        comfort_picks,
+        too_rare,
+    }
+}
+
+/// Judges the champion pool in every role, from the per champion stats.
+pub fn champion_pools(per_role_per_champ: &[(Role, DisplayChampMatches)]) -> Vec<RolePool> {
+    per_role_per_champ
+        .iter()
+        .map(|(role, champs)| {
+            let champions = champs
+                .iter()
+                .map(|(name, id, stats)| {
+                    (name.as_str(), id.as_str(), stats.games_played, stats.wins)
+                })
+                .collect::<Vec<_>>();
+            role_pool(*role, &champions)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::role_pool;
+    use crate::riot_api::json::Role;
+
+    #[test]
+    fn test_role_pool() {
+        let pool = role_pool(
+            Role::Middle,
+            &[
+                ("Ahri", "ahri", 30, 18),
+                ("Syndra", "syndra", 8, 2),
+                ("Zed", "zed", 3, 3),
+                ("Lux", "lux", 1, 0),
+            ],
+        );
+        assert_eq!(pool.games, 42);
+        assert_eq!(pool.pool_size, 4);
+        // 41 of 42 games on the top 3
+        assert!((pool.top_share - 100.0 * 41.0 / 42.0).abs() < 1e-9);
+        assert_eq!(pool.one_trick, Some("Ahri".to_string()));
+        let comfort_picks = pool
+            .comfort_picks
+            .iter()
+            .map(|champion| champion.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(comfort_picks, ["Ahri", "Syndra"]);
+        let too_rare = pool
+            .too_rare
+            .iter()
+            .map(|champion| champion.name.as_str())
+            .collect::<Vec<_>>();
+        // A 3-0 is promising, but not enough to judge
+        assert_eq!(too_rare, ["Zed", "Lux"]);
+    }
+
+    #[test]
+    fn test_role_pool_no_one_trick() {
+        let pool = role_pool(
+            Role::Top,
+            &[("Garen", "garen", 5, 3), ("Darius", "darius", 5, 2)],
+        );
+        assert_eq!(pool.one_trick, None);
+        assert!((pool.top_share - 100.0).abs() < 1e-9);
+    }
+}
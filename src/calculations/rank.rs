@@ -0,0 +1,112 @@
+use crate::{
+    rank::{RankHistory, RankSnapshot},
+    Player, State,
+};
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+/// Ladder points won or lost from `start` to `end`. Snapshots are only
+/// taken when the rank changes, so the rank at a time is the one in the last
+/// snapshot before it. If we only started tracking during the period, it
+/// counts from the first snapshot.
+pub fn lp_delta(
+    snapshots: &[RankSnapshot],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Option<i32> {
+    let first = snapshots
+        .iter()
+        .rev()
+        .find(|snapshot| snapshot.time < start)
+        .or_else(|| {
+            snapshots
+                .iter()
+                .find(|snapshot| snapshot.time >= start && snapshot.time < end)
+        })?;
+    let last = snapshots
+        .iter()
+        .rev()
+        .find(|snapshot| snapshot.time < end)?;
+    Some(last.ladder_points() - first.ladder_points())
+}
+
+fn find_history(state: &State, player: &Player) -> Option<RankHistory> {
+    let player = player.clone().normalized();
+    state
+        .rank_history
+        .list()
+        .into_iter()
+        .map(|(_, history)| history)
+        .find(|history| history.player == player)
+}
+
+/// JSON array of every snapshot of the player's rank, for the rank history
+/// chart.
+pub fn rank_chart_data(state: &State, player: &Player) -> String {
+    let snapshots = find_history(state, player)
+        .map(|history| history.snapshots)
+        .unwrap_or_default();
+    serde_json::Value::from(
+        snapshots
+            .iter()
+            .map(|snapshot| {
+                json!({
+                    "time": snapshot.time,
+                    "points": snapshot.ladder_points(),
+                    "rank": snapshot.to_string(),
+                })
+            })
+            .collect::<Vec<_>>(),
+    )
+    .to_string()
+}
+
+/// The player's last known solo queue rank, for showing next to their name.
+pub fn current_rank(state: &State, player: &Player) -> Option<String> {
+    find_history(state, player)?
+        .current()
+        .map(ToString::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lp_delta;
+    use crate::{rank::RankSnapshot, riot_api::json::Tier};
+    use chrono::{DateTime, TimeZone, Utc};
+    use test_case::test_case;
+
+    fn day(day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 3, day, 12, 0, 0).unwrap()
+    }
+
+    fn snapshot(day_of_month: u32, tier: Tier, division: &str, league_points: i32) -> RankSnapshot {
+        RankSnapshot {
+            time: day(day_of_month),
+            tier,
+            division: division.to_string(),
+            league_points,
+            wins: 0,
+            losses: 0,
+        }
+    }
+
+    fn history() -> Vec<RankSnapshot> {
+        vec![
+            snapshot(2, Tier::Gold, "II", 80),
+            // Promoted to Gold I
+            snapshot(5, Tier::Gold, "I", 10),
+            snapshot(9, Tier::Gold, "I", 55),
+            // Demoted
+            snapshot(12, Tier::Gold, "II", 75),
+        ]
+    }
+
+    #[test_case(1, 8, Some(30) ; "tracking starts in the period")]
+    #[test_case(3, 10, Some(75) ; "across a promotion")]
+    #[test_case(10, 17, Some(-80) ; "across a demotion")]
+    #[test_case(6, 8, Some(0) ; "no change in the period")]
+    #[test_case(1, 2, None ; "before tracking")]
+    fn test_lp_delta(start: u32, end: u32, expected: Option<i32>) {
+        assert_eq!(lp_delta(&history(), day(start), day(end)), expected);
+    }
+}
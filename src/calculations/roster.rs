@@ -0,0 +1,85 @@
+use super::percentage;
+use crate::riot_api::json;
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Clone, Debug, Default)]
+pub struct TogetherStats {
+    pub games: u32,
+    pub wins: u32,
+}
+
+impl TogetherStats {
+    pub fn winrate(&self) -> f64 {
+        percentage(self.wins, self.games)
+    }
+
+    fn add(&mut self, win: bool) {
+        self.games += 1;
+        if win {
+            self.wins += 1;
+        }
+    }
+}
+
+/// How a roster does when several of its members play on the same team.
+#[derive(Clone, Debug, Default)]
+pub struct RosterAggregates {
+    /// Games with at least two members on the same team
+    pub together: TogetherStats,
+    /// Keyed by how many members were on the team
+    pub per_size: BTreeMap<usize, TogetherStats>,
+}
+
+pub fn roster_aggregates<'a>(
+    matches: impl IntoIterator<Item = &'a json::Match>,
+    puuids: &HashSet<&str>,
+) -> RosterAggregates {
+    let mut aggregates = RosterAggregates::default();
+    let mut seen = HashSet::new();
+    for m in matches {
+        // Every member has the same match in their history
+        if !seen.insert(&m.metadata.match_id) {
+            continue;
+        }
+        let members = m
+            .info
+            .participants
+            .iter()
+            .filter(|p| puuids.contains(p.puuid.as_str()))
+            .map(|p| (p.team_id, p.win));
+        if let Some((size, win)) = largest_team(members) {
+            aggregates.together.add(win);
+            aggregates.per_size.entry(size).or_default().add(win);
+        }
+    }
+    aggregates
+}
+
+/// Takes the team and result of every member in a game, and returns how many
+/// members were on the same team and whether that team won. Members on
+/// opposite teams (e.g. in customs) don't count as together, and neither does
+/// a member on their own.
+fn largest_team(members: impl Iterator<Item = (i32, bool)>) -> Option<(usize, bool)> {
+    members
+        .into_group_map()
+        .into_values()
+        .map(|wins| (wins.len(), wins[0]))
+        .max_by_key(|(size, _)| *size)
+        .filter(|(size, _)| *size >= 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::largest_team;
+    use test_case::test_case;
+
+    #[test_case(&[], None)]
+    #[test_case(&[(100, true)], None)]
+    #[test_case(&[(100, true), (200, false)], None)]
+    #[test_case(&[(100, true), (100, true)], Some((2, true)))]
+    #[test_case(&[(200, false), (100, true), (200, false), (200, false)], Some((3, false)))]
+    fn test_largest_team(members: &[(i32, bool)], expected: Option<(usize, bool)>) {
+        assert_eq!(largest_team(members.iter().copied()), expected);
+    }
+}
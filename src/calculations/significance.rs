@@ -0,0 +1,102 @@
+use ordered_float::OrderedFloat;
+
+/// Differences with a p-value above this are shown, but not highlighted.
+pub const SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+/// Complementary error function, using the approximation from Abramowitz and
+/// Stegun 7.1.26, which is accurate to about 1e-7.
+fn erfc(x: f64) -> f64 {
+    if x < 0.0 {
+        return 2.0 - erfc(-x);
+    }
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    poly * (-x * x).exp()
+}
+
+/// Two-sided p-value of the Mann-Whitney U test, using the normal
+/// approximation with a correction for ties. It doesn't assume the values
+/// are normally distributed, which most per game stats aren't. Returns
+/// `None` if there's nothing to test, e.g. when all values are the same.
+#[allow(clippy::cast_precision_loss)]
+pub fn mann_whitney_p_value(a: &[f64], b: &[f64]) -> Option<f64> {
+    if a.is_empty() || b.is_empty() {
+        return None;
+    }
+    let mut values = a
+        .iter()
+        .map(|&value| (OrderedFloat(value), true))
+        .chain(b.iter().map(|&value| (OrderedFloat(value), false)))
+        .collect::<Vec<_>>();
+    values.sort_unstable_by_key(|(value, _)| *value);
+
+    // Tied values all get the average of their ranks
+    let mut rank_sum_a = 0.0;
+    let mut tie_correction = 0.0;
+    let mut start = 0;
+    while start < values.len() {
+        let end = start
+            + values[start..]
+                .iter()
+                .take_while(|(value, _)| *value == values[start].0)
+                .count();
+        let ties = (end - start) as f64;
+        let rank = (start + end + 1) as f64 / 2.0;
+        rank_sum_a += rank * values[start..end].iter().filter(|(_, in_a)| *in_a).count() as f64;
+        tie_correction += ties.powi(3) - ties;
+        start = end;
+    }
+
+    let n_a = a.len() as f64;
+    let n_b = b.len() as f64;
+    let n_total = n_a + n_b;
+    let u_statistic = rank_sum_a - n_a * (n_a + 1.0) / 2.0;
+    let mean = n_a * n_b / 2.0;
+    let variance =
+        n_a * n_b / 12.0 * ((n_total + 1.0) - tie_correction / (n_total * (n_total - 1.0)));
+    if variance <= 0.0 {
+        return None;
+    }
+    // With a continuity correction, since U only takes whole numbers
+    let z_score = ((u_statistic - mean).abs() - 0.5).max(0.0) / variance.sqrt();
+    Some(erfc(z_score / std::f64::consts::SQRT_2))
+}
+
+/// Whether the difference between the two samples is unlikely to be chance.
+pub fn is_significant(a: &[f64], b: &[f64]) -> bool {
+    mann_whitney_p_value(a, b).is_some_and(|p| p < SIGNIFICANCE_LEVEL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{erfc, mann_whitney_p_value};
+    use test_case::test_case;
+
+    #[test_case(0.0, 1.0)]
+    #[test_case(1.0, 0.157_299_207)]
+    #[test_case(2.0, 0.004_677_735)]
+    #[test_case(-1.0, 1.842_700_793)]
+    fn test_erfc(x: f64, expected: f64) {
+        assert!((erfc(x) - expected).abs() < 1e-6);
+    }
+
+    #[test_case(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0], Some(1.0))]
+    // No overlap at all: U = 0, z = (50 - 0.5) / sqrt(175)
+    #[test_case(
+        &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0],
+        &[11.0, 12.0, 13.0, 14.0, 15.0, 16.0, 17.0, 18.0, 19.0, 20.0],
+        Some(0.000_182_7)
+    )]
+    #[test_case(&[5.0, 5.0], &[5.0, 5.0, 5.0], None)]
+    #[test_case(&[], &[1.0], None)]
+    fn test_mann_whitney_p_value(a: &[f64], b: &[f64], expected: Option<f64>) {
+        let p = mann_whitney_p_value(a, b);
+        match (p, expected) {
+            (Some(p), Some(expected)) => assert!((p - expected).abs() < 1e-5, "{p}"),
+            (p, expected) => assert_eq!(p, expected),
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{annotation::MatchAnnotation, riot_api::json::Match, State};
+use serde::Deserialize;
+
+/// Which games to include in the stats, by their tags.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct TagFilter {
+    /// Only games with this tag
+    pub include_tag: Option<String>,
+    /// No games with this tag
+    pub exclude_tag: Option<String>,
+}
+
+fn non_empty(tag: Option<&str>) -> Option<&str> {
+    tag.map(str::trim).filter(|tag| !tag.is_empty())
+}
+
+impl TagFilter {
+    pub fn is_active(&self) -> bool {
+        non_empty(self.include_tag.as_deref()).is_some()
+            || non_empty(self.exclude_tag.as_deref()).is_some()
+    }
+
+    fn allows(&self, annotation: &MatchAnnotation) -> bool {
+        non_empty(self.include_tag.as_deref()).is_none_or(|tag| annotation.has_tag(tag))
+            && non_empty(self.exclude_tag.as_deref()).is_none_or(|tag| !annotation.has_tag(tag))
+    }
+
+    pub fn matches(&self, state: &State, m: &Match, puuid: &str) -> bool {
+        if !self.is_active() {
+            return true;
+        }
+        let annotation = state
+            .annotations
+            .get(&MatchAnnotation::id(&m.metadata.match_id, puuid))
+            .unwrap_or_default();
+        self.allows(&annotation)
+    }
+}
+
+/// Every tag in use, sorted, for suggestions.
+pub fn known_tags(state: &State) -> Vec<String> {
+    let mut tags = state
+        .annotations
+        .list()
+        .into_iter()
+        .flat_map(|(_, annotation)| annotation.tags)
+        .collect::<Vec<_>>();
+    tags.sort_unstable_by_key(|tag| tag.to_lowercase());
+    tags.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TagFilter;
+    use crate::annotation::MatchAnnotation;
+    use test_case::test_case;
+
+    #[test_case(None, None, "tilted", true)]
+    #[test_case(Some("Tilted"), None, "tilted, new rune page", true)]
+    #[test_case(Some("tilted"), None, "scrim practice", false)]
+    #[test_case(None, Some("tilted"), "tilted", false)]
+    #[test_case(None, Some("tilted"), "", true)]
+    #[test_case(Some(""), Some(" "), "", true)]
+    fn test_allows(include: Option<&str>, exclude: Option<&str>, tags: &str, expected: bool) {
+        let filter = TagFilter {
+            include_tag: include.map(ToString::to_string),
+            exclude_tag: exclude.map(ToString::to_string),
+        };
+        assert_eq!(filter.allows(&MatchAnnotation::parse("", tags)), expected);
+    }
+}
@@ -0,0 +1,323 @@
+use super::{
+    champion_name_by_id, metrics::game_metrics, timeline_get_player_id, Side, KEY_MINUTES,
+};
+use crate::{
+    riot_api::json::{self, Role},
+    State,
+};
+use chrono::TimeDelta;
+use itertools::Itertools;
+use std::collections::HashSet;
+use strum::IntoEnumIterator;
+
+/// Per role metrics, averaged over the games. Index 0 is always the first
+/// team, 1 the second.
+#[derive(Clone, Debug)]
+pub struct RoleComparison {
+    pub role: Role,
+    pub players: [String; 2],
+    pub metrics: Vec<(&'static str, [f64; 2])>,
+}
+
+/// How far ahead the first team's player was on their lane opponent, on
+/// average.
+#[derive(Clone, Debug)]
+pub struct LaneDiff {
+    pub role: Role,
+    pub minute: u32,
+    pub gold_diff: f64,
+    pub xp_diff: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct ObjectiveRow {
+    pub name: &'static str,
+    pub kills: [i32; 2],
+    pub firsts: [u32; 2],
+}
+
+#[derive(Clone, Debug)]
+pub struct TeamDraft {
+    pub side: Side,
+    pub win: bool,
+    pub bans: Vec<String>,
+    pub picks: Vec<(Role, String)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct GameDraft {
+    pub match_id: String,
+    pub teams: [TeamDraft; 2],
+}
+
+#[derive(Clone, Debug)]
+pub struct TeamReport {
+    pub wins: [u32; 2],
+    pub games: usize,
+    pub roles: Vec<RoleComparison>,
+    pub lane_diffs: Vec<LaneDiff>,
+    pub objectives: Vec<ObjectiveRow>,
+    pub drafts: Vec<GameDraft>,
+}
+
+/// Teams can switch sides between games, so the first team is whoever was on
+/// blue side in the first game, and in later games it's the side with the
+/// most of those players on it. Returns the team IDs in the order of the
+/// report.
+fn team_ids(m: &json::Match, first_team: &HashSet<&str>) -> [i32; 2] {
+    let on_blue = m
+        .info
+        .participants
+        .iter()
+        .filter(|p| p.team_id == 100 && first_team.contains(p.puuid.as_str()))
+        .count();
+    if on_blue * 2 >= first_team.len() {
+        [100, 200]
+    } else {
+        [200, 100]
+    }
+}
+
+/// Gold and XP difference between two players at `minute`.
+fn lane_diff_at(timeline: &json::Timeline, a: usize, b: usize, minute: u32) -> Option<(i32, i32)> {
+    let frame = timeline
+        .info
+        .frames
+        .iter()
+        .find(|f| f.timestamp >= TimeDelta::minutes(i64::from(minute)))?;
+    let a = frame.participant_frames.get(&a)?;
+    let b = frame.participant_frames.get(&b)?;
+    Some((a.total_gold - b.total_gold, a.xp - b.xp))
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let count = values.len() as f64;
+    values.iter().sum::<f64>() / count
+}
+
+/// Adds the objectives of `team` to the totals of the team at `index`.
+fn count_objectives(objectives: &mut [ObjectiveRow], index: usize, team: &json::Team) {
+    let o = &team.objectives;
+    for (row, objective) in objectives.iter_mut().zip([
+        &o.champion,
+        &o.tower,
+        &o.inhibitor,
+        &o.dragon,
+        &o.horde,
+        &o.rift_herald,
+        &o.baron,
+    ]) {
+        row.kills[index] += objective.kills;
+        if objective.first {
+            row.firsts[index] += 1;
+        }
+    }
+}
+
+fn team_draft(m: &json::Match, team: &json::Team, roles: &[Role]) -> TeamDraft {
+    let picks = roles
+        .iter()
+        .filter_map(|role| {
+            m.info
+                .participants
+                .iter()
+                .find(|p| p.team_id == team.id && p.team_position == *role)
+                .map(|p| (*role, p.champion_name.clone()))
+        })
+        .collect();
+    TeamDraft {
+        side: if team.id == 100 {
+            Side::Blue
+        } else {
+            Side::Red
+        },
+        win: team.win,
+        bans: team
+            .bans
+            .iter()
+            .sorted_by_key(|ban| ban.pick_turn)
+            // -1 is no ban
+            .filter(|ban| ban.champion_id >= 0)
+            .map(|ban| champion_name_by_id(ban.champion_id))
+            .collect(),
+        picks,
+    }
+}
+
+/// Averages the metrics of each team per role, `values` holds the metrics of
+/// every game in the same order as the names below.
+fn role_comparisons(
+    roles: &[Role],
+    players: Vec<[Vec<String>; 2]>,
+    values: Vec<[Vec<[f64; 6]>; 2]>,
+) -> Vec<RoleComparison> {
+    let metric_names = [
+        "KDA",
+        "CS/min",
+        "Gold share",
+        "Damage share",
+        "Kill participation",
+        "Vision score/min",
+    ];
+    roles
+        .iter()
+        .zip(players)
+        .zip(values)
+        .map(|((role, players), values)| RoleComparison {
+            role: *role,
+            players: players.map(|names| names.join(" / ")),
+            metrics: metric_names
+                .iter()
+                .enumerate()
+                .map(|(metric_index, name)| {
+                    let averages = [0, 1].map(|index| {
+                        mean(
+                            &values[index]
+                                .iter()
+                                .map(|game| game[metric_index])
+                                .collect::<Vec<_>>(),
+                        )
+                    });
+                    (*name, averages)
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Averages the gold and XP diffs per role and key minute, `diffs` is indexed
+/// by role and then minute.
+fn average_lane_diffs(roles: &[Role], diffs: Vec<Vec<(i32, i32)>>) -> Vec<LaneDiff> {
+    roles
+        .iter()
+        .cartesian_product(KEY_MINUTES)
+        .zip(diffs)
+        .filter(|(_, diffs)| !diffs.is_empty())
+        .map(|((role, minute), diffs)| LaneDiff {
+            role: *role,
+            minute,
+            gold_diff: mean(
+                &diffs
+                    .iter()
+                    .map(|(gold, _)| f64::from(*gold))
+                    .collect::<Vec<_>>(),
+            ),
+            xp_diff: mean(
+                &diffs
+                    .iter()
+                    .map(|(_, xp)| f64::from(*xp))
+                    .collect::<Vec<_>>(),
+            ),
+        })
+        .collect()
+}
+
+/// Compares both teams in a set of games between the same two teams, like a
+/// scrim block. `matches` has to be sorted by start time.
+pub fn team_report(state: &State, matches: &[json::Match]) -> TeamReport {
+    let first_team = matches[0]
+        .info
+        .participants
+        .iter()
+        .filter(|p| p.team_id == 100)
+        .map(|p| p.puuid.as_str())
+        .collect::<HashSet<_>>();
+    let roles = Role::iter()
+        .filter(|role| *role != Role::None)
+        .collect::<Vec<_>>();
+
+    let mut wins = [0, 0];
+    let mut players = vec![[vec![], vec![]]; roles.len()];
+    // Per role, per team, per metric, the value in each game
+    let mut metric_values = vec![[vec![], vec![]]; roles.len()];
+    let mut lane_diffs = vec![vec![]; roles.len() * KEY_MINUTES.len()];
+    let mut objectives = [
+        "Champion kills",
+        "Towers",
+        "Inhibitors",
+        "Dragons",
+        "Void grubs",
+        "Rift heralds",
+        "Barons",
+    ]
+    .map(|name| ObjectiveRow {
+        name,
+        kills: [0, 0],
+        firsts: [0, 0],
+    });
+    let mut drafts = vec![];
+
+    for m in matches {
+        let timeline = state.timeline_per_match.get(&m.metadata.match_id).unwrap();
+        let ids = team_ids(m, &first_team);
+        let mut team_drafts = vec![];
+        for (index, team_id) in ids.iter().enumerate() {
+            let Some(team) = m.info.teams.iter().find(|t| t.id == *team_id) else {
+                continue;
+            };
+            if team.win {
+                wins[index] += 1;
+            }
+            count_objectives(&mut objectives, index, team);
+            team_drafts.push(team_draft(m, team, &roles));
+        }
+        if let Ok(teams) = team_drafts.try_into() {
+            drafts.push(GameDraft {
+                match_id: m.metadata.match_id.clone(),
+                teams,
+            });
+        }
+
+        for (role_index, role) in roles.iter().enumerate() {
+            let participants = ids.map(|team_id| {
+                m.info
+                    .participants
+                    .iter()
+                    .find(|p| p.team_id == team_id && p.team_position == *role)
+            });
+            for (index, participant) in participants.iter().enumerate() {
+                let Some(participant) = participant else {
+                    continue;
+                };
+                let name = format!(
+                    "{}#{}",
+                    participant.riot_id_game_name, participant.riot_id_tagline
+                );
+                if !players[role_index][index].contains(&name) {
+                    players[role_index][index].push(name);
+                }
+                let metrics = game_metrics(m, &timeline, &participant.puuid);
+                metric_values[role_index][index].push([
+                    metrics.kda,
+                    metrics.cs_per_minute,
+                    metrics.gold_share,
+                    metrics.champion_damage_share,
+                    metrics.kill_participation,
+                    metrics.vision_score_per_minute,
+                ]);
+            }
+            if let [Some(a), Some(b)] = participants {
+                let a = timeline_get_player_id(&timeline, &a.puuid);
+                let b = timeline_get_player_id(&timeline, &b.puuid);
+                for (minute_index, minute) in KEY_MINUTES.iter().enumerate() {
+                    if let Some(diff) = lane_diff_at(&timeline, a, b, *minute) {
+                        lane_diffs[role_index * KEY_MINUTES.len() + minute_index].push(diff);
+                    }
+                }
+            }
+        }
+    }
+
+    TeamReport {
+        wins,
+        games: matches.len(),
+        roles: role_comparisons(&roles, players, metric_values),
+        lane_diffs: average_lane_diffs(&roles, lane_diffs),
+        objectives: Vec::from(objectives),
+        drafts,
+    }
+}
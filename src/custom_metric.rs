@@ -0,0 +1,25 @@
+use crate::calculations::Summary;
+use serde::{Deserialize, Serialize};
+
+fn default_summary() -> Summary {
+    Summary::Median
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// A metric defined by the user, as an expression over a game's numbers,
+/// e.g. `(kills + assists) / game_minutes`. Kept in
+/// `{DATA_DIR}/custom_metrics.json`, which can also be edited by hand.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct CustomMetricDefinition {
+    pub name: String,
+    pub expression: String,
+    #[serde(default = "default_summary")]
+    pub summary: Summary,
+    #[serde(default = "default_true")]
+    pub up_is_good: bool,
+    #[serde(default)]
+    pub percent: bool,
+}
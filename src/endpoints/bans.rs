@@ -0,0 +1,57 @@
+use crate::{
+    calculations::{default_start, BanAnalysis},
+    fetcher::{check_or_start_fetching, RedirectOrContinue},
+    internal_server_error,
+    riot_api::json::Role,
+    Player, PlayerRoleChamp, State, CHAMP_NAMES,
+};
+use actix_web::{routes, web, Either, HttpRequest, Responder, Result as ActixResult};
+use askama_actix::Template;
+use log::debug;
+
+#[derive(Template)]
+#[template(path = "bans.html")]
+struct DisplayData {
+    player: Player,
+    role: Option<Role>,
+    champion: Option<String>,
+    analysis: BanAnalysis,
+}
+
+#[routes]
+#[get("/bans/{region}/{game_name}/{tag_line}")]
+#[get("/bans/{region}/{game_name}/{tag_line}/{role}")]
+#[get("/bans/{region}/{game_name}/{tag_line}/{role}/{champion}")]
+pub async fn page(
+    state: State,
+    request: HttpRequest,
+    path: web::Path<PlayerRoleChamp>,
+) -> ActixResult<impl Responder> {
+    let (mut player, role, champion) = path.into_inner().into();
+    debug!("Getting bans for {player} in {role:?} as {champion:?}");
+    if let RedirectOrContinue::Redirect(redirect) = check_or_start_fetching(
+        state.clone(),
+        &player,
+        default_start(),
+        Some(request.path()),
+    )
+    .await
+    .map_err(internal_server_error)?
+    {
+        return Ok(Either::Left(redirect));
+    }
+    let analysis = crate::calculations::calc_bans(state, &mut player, role, champion.as_deref())
+        .await
+        .map_err(internal_server_error)?;
+    let champion = champion.map(|c| (*CHAMP_NAMES.get(&c).unwrap()).to_string());
+    Ok(Either::Right(
+        DisplayData {
+            player,
+            role,
+            champion,
+            analysis,
+        }
+        .customize()
+        .insert_header(("content-type", "text/html")),
+    ))
+}
@@ -1,25 +1,36 @@
 use crate::{
-    calculations::GroupStats,
+    calculations::{current_rank, default_start, GroupStats, Period, TeamReport},
     fetcher::{check_or_start_fetching, RedirectOrContinue},
-    internal_server_error,
+    from_str_value, internal_server_error,
     riot_api::json::Role,
-    LeagueRegion, Player, State, CHAMP_NAMES,
+    LeagueRegion, Player, PlayerRoleChamp, State, CHAMP_NAMES,
+};
+use actix_web::{
+    error::ErrorBadRequest,
+    routes,
+    web::{self, Redirect},
+    Either, HttpRequest, Responder, Result as ActixResult,
 };
-use actix_web::{routes, web, Either, HttpRequest, Responder, Result as ActixResult};
 use askama_actix::Template;
+use chrono::{NaiveDate, NaiveTime};
 use itertools::Itertools;
 use log::debug;
+use reqwest::Url;
 use serde::Deserialize;
 use std::{
     cmp::Ordering,
     collections::{HashMap, HashSet},
 };
+use strum::IntoEnumIterator;
+
+// A whole team plus a few subs should fit, but not much more than that.
+const MAX_PLAYERS: usize = 10;
 
 struct ChampionStats {
     name: String,
     id: String,
     total_games: u32,
-    stats: [Option<GroupStats>; 2],
+    stats: Vec<Option<GroupStats>>,
 }
 
 type PerRoleChampStats = Vec<(Role, Vec<ChampionStats>)>;
@@ -27,31 +38,66 @@ type PerGroupChampStats = HashMap<String, PerRoleChampStats>;
 
 struct ChampionStatsIntermediate {
     id: String,
-    player1: Option<GroupStats>,
-    player2: Option<GroupStats>,
+    stats: Vec<Option<GroupStats>>,
 }
 
 type ChampStatsMap = HashMap<String, ChampionStatsIntermediate>;
 type RoleStatsMap = HashMap<Role, ChampStatsMap>;
 type GroupStatsMap = HashMap<String, RoleStatsMap>;
+// Keyed by the index of the column, and the group ID
+type ColumnGroupStats = HashMap<(usize, String), GroupStats>;
 
 #[derive(Template)]
 #[template(path = "compare2.html", escape = "none")]
 struct DisplayData {
-    players: [Player; 2],
+    // Who or what is being compared, for the title
+    subject: String,
+    // One label per column, either a player or a period
+    columns: Vec<String>,
+    // Which region the player in each column is on, for links
+    column_regions: Vec<LeagueRegion>,
+    // The solo queue rank of the player in each column, if it's a player
+    // and we know it
+    column_ranks: Vec<Option<String>>,
+    base_url: String,
+    // Appended to links, so they keep the same periods
+    query: String,
     role: Option<Role>,
     champion_name: Option<String>,
     champion_id: Option<String>,
-    data: HashMap<(Player, String), GroupStats>,
+    data: ColumnGroupStats,
     group_titles_and_ids: Vec<(String, String)>,
     per_group_per_role_per_champ: PerGroupChampStats,
 }
 
 impl DisplayData {
+    fn metric_names(&self) -> Vec<&str> {
+        // Every group has the same metrics
+        self.data
+            .values()
+            .next()
+            .map(|group| {
+                group
+                    .shown_metrics()
+                    .map(|metric| metric.name.as_str())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+    // We have to pass by ref, because that's what Askama generates
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    fn get_group<'a>(&'a self, column_index: &usize, name: &&String) -> Option<&'a GroupStats> {
+        self.data.get(&(*column_index, (*name).to_string()))
+    }
     // We have to pass by ref, because that's what Askama generates
     #[allow(clippy::trivially_copy_pass_by_ref)]
-    fn get_group<'a>(&'a self, player: &Player, name: &&String) -> Option<&'a GroupStats> {
-        self.data.get(&(player.clone(), (*name).to_string()))
+    fn column_region(&self, column_index: &usize) -> LeagueRegion {
+        self.column_regions[*column_index]
+    }
+    // We have to pass by ref, because that's what Askama generates
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    fn column_rank(&self, column_index: &usize) -> Option<&str> {
+        self.column_ranks[*column_index].as_deref()
     }
     // We have to pass by ref, because that's what Askama generates
     #[allow(clippy::trivially_copy_pass_by_ref)]
@@ -60,46 +106,72 @@ impl DisplayData {
     }
 }
 
-#[derive(Deserialize)]
-struct Params2 {
-    region1: LeagueRegion,
-    game_name1: String,
-    tag_line1: String,
-    region2: LeagueRegion,
-    game_name2: String,
-    tag_line2: String,
+struct Params {
+    players: Vec<Player>,
     role: Option<Role>,
     champion: Option<String>,
 }
 
-impl Params2 {
-    fn into(self) -> (Player, Player, Option<Role>, Option<String>) {
-        (
-            Player {
-                region: self.region1,
-                game_name: self.game_name1,
-                tag_line: self.tag_line1,
-            }
-            .normalized(),
-            Player {
-                region: self.region2,
-                game_name: self.game_name2,
-                tag_line: self.tag_line2,
+impl Params {
+    /// Parses `{region}/{game_name}/{tag_line}/vs/{region}/{game_name}/{tag_line}[/vs/...][/{role}[/{champion}]]`
+    fn parse(tail: &str) -> Result<Self, String> {
+        let segments = tail
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>();
+        let per_player = segments
+            .split(|segment| *segment == "vs")
+            .collect::<Vec<_>>();
+        if per_player.len() < 2 {
+            return Err("Need at least two players to compare".to_string());
+        }
+        if per_player.len() > MAX_PLAYERS {
+            return Err(format!("Can compare at most {MAX_PLAYERS} players"));
+        }
+        let mut players = vec![];
+        let mut role = None;
+        let mut champion = None;
+        for (position, player_segments) in per_player.into_iter().with_position() {
+            let is_last = matches!(position, itertools::Position::Last);
+            let (player_segments, rest) = match (player_segments.len(), is_last) {
+                (3, _) => (player_segments, &[][..]),
+                (4 | 5, true) => player_segments.split_at(3),
+                _ => return Err(format!("Invalid player: {}", player_segments.join("/"))),
+            };
+            let region = from_str_value::<LeagueRegion>(player_segments[0])
+                .map_err(|e| format!("Invalid region {}: {e}", player_segments[0]))?;
+            players.push(
+                Player {
+                    region,
+                    game_name: player_segments[1].to_string(),
+                    tag_line: player_segments[2].to_string(),
+                }
+                .normalized(),
+            );
+            if let Some(role_segment) = rest.first() {
+                role = Some(
+                    from_str_value::<Role>(role_segment)
+                        .map_err(|e| format!("Invalid role {role_segment}: {e}"))?,
+                );
             }
-            .normalized(),
-            self.role,
-            self.champion,
-        )
+            champion = rest.get(1).map(|c| crate::normalize_champion_name(c));
+        }
+        Ok(Self {
+            players,
+            role,
+            champion,
+        })
     }
 }
 
 async fn do_player(
     state: State,
+    player_index: usize,
     player: &mut Player,
     role: Option<Role>,
     champion: Option<&str>,
     group_titles_and_ids: &mut HashSet<(String, String)>,
-) -> ActixResult<HashMap<(Player, String), GroupStats>> {
+) -> ActixResult<ColumnGroupStats> {
     let groups = crate::calculations::calc_stats(state.clone(), player, role, champion)
         .await
         .map_err(internal_server_error)?;
@@ -108,24 +180,47 @@ async fn do_player(
     }
     Ok(groups
         .into_iter()
-        .map(|group| ((player.clone(), group.id.clone()), group))
+        .map(|group| ((player_index, group.id.clone()), group))
         .collect::<HashMap<_, _>>())
 }
 
+fn player_label(player: &Player) -> String {
+    format!("{}#{}", player.game_name, player.tag_line)
+}
+
+/// Joins with commas, except for the last one, which gets "and".
+fn join_and(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [only] => only.clone(),
+        [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+    }
+}
+
+fn base_url(players: &[Player]) -> String {
+    let players = players
+        .iter()
+        .map(|player| format!("{}/{}/{}", player.region, player.game_name, player.tag_line))
+        .join("/vs/");
+    format!("/compare/{players}")
+}
+
 #[routes]
-#[get("/compare/{region1}/{game_name1}/{tag_line1}/vs/{region2}/{game_name2}/{tag_line2}")]
-#[get("/compare/{region1}/{game_name1}/{tag_line1}/vs/{region2}/{game_name2}/{tag_line2}/{role}")]
-#[get("/compare/{region1}/{game_name1}/{tag_line1}/vs/{region2}/{game_name2}/{tag_line2}/{role}/{champion}")]
-pub async fn page2(
+#[get("/compare/{tail:.*}")]
+pub async fn page(
     state: State,
     request: HttpRequest,
-    path: web::Path<Params2>,
+    path: web::Path<String>,
 ) -> ActixResult<impl Responder> {
-    let (mut p1, mut p2, role, champion) = path.into_inner().into();
-    for player in [&p1, &p2] {
+    let Params {
+        mut players,
+        role,
+        champion,
+    } = Params::parse(&path.into_inner()).map_err(ErrorBadRequest)?;
+    for player in &players {
         debug!("Getting stats for {player} in {role:?} as {champion:?}");
         if let RedirectOrContinue::Redirect(redirect) =
-            check_or_start_fetching(state.clone(), player, Some(request.path()))
+            check_or_start_fetching(state.clone(), player, default_start(), Some(request.path()))
                 .await
                 .map_err(internal_server_error)?
         {
@@ -133,27 +228,23 @@ pub async fn page2(
         }
     }
     let mut group_titles_and_ids = HashSet::new();
-    let mut p1d = do_player(
-        state.clone(),
-        &mut p1,
-        role,
-        champion.as_deref(),
-        &mut group_titles_and_ids,
-    )
-    .await
-    .map_err(internal_server_error)?;
-    let p2d = do_player(
-        state.clone(),
-        &mut p2,
-        role,
-        champion.as_deref(),
-        &mut group_titles_and_ids,
-    )
-    .await
-    .map_err(internal_server_error)?;
+    let mut data = HashMap::new();
+    for (player_index, player) in players.iter_mut().enumerate() {
+        data.extend(
+            do_player(
+                state.clone(),
+                player_index,
+                player,
+                role,
+                champion.as_deref(),
+                &mut group_titles_and_ids,
+            )
+            .await?,
+        );
+    }
     let mut per_group_per_role_per_champ: GroupStatsMap = HashMap::new();
 
-    compare_players(&mut p1d, &p2d, &mut per_group_per_role_per_champ);
+    compare_columns(&mut data, players.len(), &mut per_group_per_role_per_champ);
 
     let mut group_titles_and_ids = group_titles_and_ids
         .into_iter()
@@ -167,12 +258,24 @@ pub async fn page2(
         .as_ref()
         .map(|c| (*CHAMP_NAMES.get(c).unwrap()).to_string());
 
-    let mut data = p1d;
-    data.extend(p2d);
+    let subject = join_and(
+        &players
+            .iter()
+            .map(|player| format!("{} on {}", player_label(player), player.region))
+            .collect::<Vec<_>>(),
+    );
 
     Ok(Either::Right(
         DisplayData {
-            players: [p1, p2],
+            subject,
+            columns: players.iter().map(player_label).collect(),
+            column_regions: players.iter().map(|player| player.region).collect(),
+            column_ranks: players
+                .iter()
+                .map(|player| current_rank(&state, player))
+                .collect(),
+            base_url: base_url(&players),
+            query: String::new(),
             role,
             champion_name,
             champion_id: champion,
@@ -187,6 +290,362 @@ pub async fn page2(
     ))
 }
 
+#[derive(Deserialize)]
+struct PeriodsQuery {
+    from1: Option<NaiveDate>,
+    to1: Option<NaiveDate>,
+    patch1: Option<String>,
+    from2: Option<NaiveDate>,
+    to2: Option<NaiveDate>,
+    patch2: Option<String>,
+}
+
+/// Turns the query parameters for one period into a `Period` and a label for
+/// it. Both dates are inclusive.
+fn parse_period(
+    number: u8,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    patch: Option<String>,
+) -> Result<(Period, String), String> {
+    let mut label_parts = vec![];
+    if let Some(patch) = &patch {
+        label_parts.push(format!("patch {patch}"));
+    }
+    match (from, to) {
+        (Some(from), Some(to)) => label_parts.push(format!("{from} to {to}")),
+        (Some(from), None) => label_parts.push(format!("since {from}")),
+        (None, Some(to)) => label_parts.push(format!("until {to}")),
+        (None, None) if patch.is_none() => {
+            return Err(format!(
+                "Period {number} needs at least one of from{number}, to{number} or patch{number}"
+            ));
+        }
+        (None, None) => {}
+    }
+    let to = match to {
+        Some(to) => Some(
+            to.succ_opt()
+                .ok_or_else(|| format!("Invalid to{number}: {to}"))?,
+        ),
+        None => None,
+    };
+    let period = Period {
+        from: from.map(|from| from.and_time(NaiveTime::MIN).and_utc()),
+        to: to.map(|to| to.and_time(NaiveTime::MIN).and_utc()),
+        patch,
+    };
+    Ok((period, label_parts.join(", ")))
+}
+
+/// Compares a player to themselves, in two different periods. The periods
+/// are given as `from1`, `to1`, `patch1`, `from2`, `to2` and `patch2` query
+/// parameters, with dates as YYYY-MM-DD.
+#[routes]
+#[get("/compare-self/{region}/{game_name}/{tag_line}")]
+#[get("/compare-self/{region}/{game_name}/{tag_line}/{role}")]
+#[get("/compare-self/{region}/{game_name}/{tag_line}/{role}/{champion}")]
+pub async fn self_page(
+    state: State,
+    request: HttpRequest,
+    path: web::Path<PlayerRoleChamp>,
+    query: web::Query<PeriodsQuery>,
+) -> ActixResult<impl Responder> {
+    let (mut player, role, champion) = path.into_inner().into();
+    let PeriodsQuery {
+        from1,
+        to1,
+        patch1,
+        from2,
+        to2,
+        patch2,
+    } = query.into_inner();
+    let periods = [
+        parse_period(1, from1, to1, patch1).map_err(ErrorBadRequest)?,
+        parse_period(2, from2, to2, patch2).map_err(ErrorBadRequest)?,
+    ];
+    debug!("Getting stats for {player} in {role:?} as {champion:?} for {periods:?}");
+    let start = periods
+        .iter()
+        .map(|(period, _)| period.start())
+        .min()
+        .unwrap();
+    let query = match request.query_string() {
+        "" => String::new(),
+        query_string => format!("?{query_string}"),
+    };
+    if let RedirectOrContinue::Redirect(redirect) = check_or_start_fetching(
+        state.clone(),
+        &player,
+        start,
+        Some(&format!("{}{query}", request.path())),
+    )
+    .await
+    .map_err(internal_server_error)?
+    {
+        return Ok(Either::Left(redirect));
+    }
+    let mut group_titles_and_ids = HashSet::new();
+    let mut data = HashMap::new();
+    for (column_index, (period, _)) in periods.iter().enumerate() {
+        let group = crate::calculations::calc_period_stats(
+            state.clone(),
+            &mut player,
+            period,
+            role,
+            champion.as_deref(),
+        )
+        .await
+        .map_err(internal_server_error)?;
+        if let Some(group) = group {
+            group_titles_and_ids.insert((group.title.clone(), group.id.clone()));
+            data.insert((column_index, group.id.clone()), group);
+        }
+    }
+    let mut per_group_per_role_per_champ: GroupStatsMap = HashMap::new();
+    compare_columns(&mut data, periods.len(), &mut per_group_per_role_per_champ);
+
+    let champion_name = champion
+        .as_ref()
+        .map(|c| (*CHAMP_NAMES.get(c).unwrap()).to_string());
+    let columns = periods
+        .into_iter()
+        .map(|(_, label)| label)
+        .collect::<Vec<_>>();
+    let subject = format!(
+        "{} on {} between {}",
+        player_label(&player),
+        player.region,
+        join_and(&columns),
+    );
+
+    Ok(Either::Right(
+        DisplayData {
+            subject,
+            column_regions: vec![player.region; columns.len()],
+            // Every column is the same player
+            column_ranks: vec![None; columns.len()],
+            columns,
+            base_url: format!(
+                "/compare-self/{}/{}/{}",
+                player.region, player.game_name, player.tag_line
+            ),
+            query,
+            role,
+            champion_name,
+            champion_id: champion,
+            data,
+            group_titles_and_ids: group_titles_and_ids.into_iter().sorted().collect(),
+            per_group_per_role_per_champ: get_per_group_per_role_per_champ(
+                per_group_per_role_per_champ,
+            ),
+        }
+        .customize()
+        .insert_header(("content-type", "text/html")),
+    ))
+}
+
+#[derive(Template)]
+#[template(path = "scrim.html")]
+struct ScrimFormData {
+    regions: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ScrimQuery {
+    region: Option<LeagueRegion>,
+    // Separated by whitespace or commas
+    matches: Option<String>,
+    tournament_code: Option<String>,
+    // Either players (the default) or teams
+    view: Option<String>,
+}
+
+impl ScrimQuery {
+    fn match_ids(&self) -> Vec<String> {
+        self.matches
+            .as_deref()
+            .unwrap_or_default()
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|match_id| !match_id.is_empty())
+            .map(str::to_uppercase)
+            .collect()
+    }
+
+    fn tournament_code(&self) -> Option<&str> {
+        self.tournament_code
+            .as_deref()
+            .map(str::trim)
+            .filter(|code| !code.is_empty())
+    }
+}
+
+#[derive(Deserialize)]
+struct ScrimPath {
+    region: LeagueRegion,
+    role: Option<Role>,
+    champion: Option<String>,
+}
+
+/// The form to pick scrim games, which sends the region along as a query
+/// parameter, so we redirect that to the page with the region in the path.
+#[routes]
+#[get("/scrim")]
+pub async fn scrim_form(query: web::Query<ScrimQuery>) -> ActixResult<impl Responder> {
+    let query = query.into_inner();
+    if let Some(region) = query.region {
+        // Only used to get the query properly encoded, so the host doesn't
+        // matter.
+        let path = match query.view.as_deref() {
+            Some("teams") => "/scrim-teams/",
+            _ => "/scrim/",
+        };
+        let mut url = Url::parse("http://localhost")
+            .and_then(|url| url.join(path))
+            .and_then(|url| url.join(&region.to_string()))
+            .map_err(internal_server_error)?;
+        url.query_pairs_mut()
+            .append_pair("matches", &query.match_ids().join(","))
+            .append_pair(
+                "tournament_code",
+                query.tournament_code().unwrap_or_default(),
+            );
+        let location = format!("{}?{}", url.path(), url.query().unwrap_or_default());
+        return Ok(Either::Left(Redirect::to(location).see_other()));
+    }
+    Ok(Either::Right(
+        ScrimFormData {
+            regions: LeagueRegion::iter()
+                .map(|region| region.to_string())
+                .collect(),
+        }
+        .customize()
+        .insert_header(("content-type", "text/html")),
+    ))
+}
+
+/// Compares everyone who played in a set of custom or tournament games, using
+/// only those games. The games are given as `matches` (IDs separated by
+/// commas) and/or `tournament_code` query parameters.
+#[routes]
+#[get("/scrim/{region}")]
+#[get("/scrim/{region}/{role}")]
+#[get("/scrim/{region}/{role}/{champion}")]
+pub async fn scrim_page(
+    state: State,
+    request: HttpRequest,
+    path: web::Path<ScrimPath>,
+    query: web::Query<ScrimQuery>,
+) -> ActixResult<impl Responder> {
+    let ScrimPath {
+        region,
+        role,
+        champion,
+    } = path.into_inner();
+    let champion = champion.as_deref().map(crate::normalize_champion_name);
+    let match_ids = query.match_ids();
+    let tournament_code = query.tournament_code();
+    if match_ids.is_empty() && tournament_code.is_none() {
+        return Err(ErrorBadRequest("Need match IDs or a tournament code"));
+    }
+    debug!(
+        "Getting scrim stats for {match_ids:?} and {tournament_code:?} in {role:?} as {champion:?}"
+    );
+    let players = crate::calculations::calc_scrim(
+        state.clone(),
+        region,
+        &match_ids,
+        tournament_code,
+        role,
+        champion.as_deref(),
+    )
+    .await
+    .map_err(internal_server_error)?;
+
+    let mut group_titles_and_ids = HashSet::new();
+    let mut data = HashMap::new();
+    for (column_index, scrim_player) in players.iter().enumerate() {
+        let group = &scrim_player.stats;
+        group_titles_and_ids.insert((group.title.clone(), group.id.clone()));
+        data.insert((column_index, group.id.clone()), group.clone());
+    }
+    let mut per_group_per_role_per_champ: GroupStatsMap = HashMap::new();
+    compare_columns(&mut data, players.len(), &mut per_group_per_role_per_champ);
+
+    let champion_name = champion
+        .as_ref()
+        .map(|c| (*CHAMP_NAMES.get(c).unwrap()).to_string());
+    let games = players
+        .iter()
+        .map(|scrim_player| scrim_player.stats.games_played)
+        .max()
+        .unwrap_or_default();
+    let subject = format!("the players in {games} scrim games on {region}");
+
+    Ok(DisplayData {
+        subject,
+        columns: players
+            .iter()
+            .map(|scrim_player| player_label(&scrim_player.player))
+            .collect(),
+        column_regions: vec![region; players.len()],
+        column_ranks: players
+            .iter()
+            .map(|scrim_player| current_rank(&state, &scrim_player.player))
+            .collect(),
+        base_url: format!("/scrim/{region}"),
+        query: format!("?{}", request.query_string()),
+        role,
+        champion_name,
+        champion_id: champion,
+        data,
+        group_titles_and_ids: group_titles_and_ids.into_iter().sorted().collect(),
+        per_group_per_role_per_champ: get_per_group_per_role_per_champ(
+            per_group_per_role_per_champ,
+        ),
+    }
+    .customize()
+    .insert_header(("content-type", "text/html")))
+}
+
+#[derive(Template)]
+#[template(path = "scrim_teams.html")]
+struct TeamReportData {
+    region: LeagueRegion,
+    report: TeamReport,
+}
+
+/// Compares both teams in a set of custom or tournament games, with the same
+/// query parameters as `scrim_page`.
+#[routes]
+#[get("/scrim-teams/{region}")]
+pub async fn scrim_teams_page(
+    state: State,
+    path: web::Path<LeagueRegion>,
+    query: web::Query<ScrimQuery>,
+) -> ActixResult<impl Responder> {
+    let region = path.into_inner();
+    let match_ids = query.match_ids();
+    let tournament_code = query.tournament_code();
+    if match_ids.is_empty() && tournament_code.is_none() {
+        return Err(ErrorBadRequest("Need match IDs or a tournament code"));
+    }
+    debug!("Getting team report for {match_ids:?} and {tournament_code:?}");
+    let report = crate::calculations::calc_team_report(state, region, &match_ids, tournament_code)
+        .await
+        .map_err(internal_server_error)?;
+    Ok(TeamReportData { region, report }
+        .customize()
+        .insert_header(("content-type", "text/html")))
+}
+
+fn games_played(stats: &[Option<GroupStats>]) -> u32 {
+    stats
+        .iter()
+        .map(|s| s.as_ref().map_or(0, |s| s.games_played))
+        .sum()
+}
+
 fn get_per_group_per_role_per_champ(
     per_group_per_role_per_champ: GroupStatsMap,
 ) -> PerGroupChampStats {
@@ -201,50 +660,21 @@ fn get_per_group_per_role_per_champ(
                         let champs = champs
                             .into_iter()
                             .map(
-                                |(
+                                |(name, ChampionStatsIntermediate { id, stats })| ChampionStats {
                                     name,
-                                    ChampionStatsIntermediate {
-                                        id,
-                                        player1,
-                                        player2,
-                                    },
-                                )| {
-                                    let total_games =
-                                        player1.as_ref().map_or(0, |s| s.games_played)
-                                            + player2.as_ref().map_or(0, |s| s.games_played);
-                                    ChampionStats {
-                                        name,
-                                        id,
-                                        total_games,
-                                        stats: [player1, player2],
-                                    }
+                                    id,
+                                    total_games: games_played(&stats),
+                                    stats,
                                 },
                             )
                             // Sort by games played, descending
-                            .sorted_by_key(|cs| {
-                                -i64::from(
-                                    cs.stats
-                                        .iter()
-                                        .map(|s| s.as_ref().map_or(0, |s| s.games_played))
-                                        .sum::<u32>(),
-                                )
-                            })
+                            .sorted_by_key(|cs| -i64::from(cs.total_games))
                             .collect::<Vec<_>>();
                         (role, champs)
                     })
                     // Sort by games played
                     .sorted_by_cached_key(|per_role| {
-                        -i64::from(
-                            per_role
-                                .1
-                                .iter()
-                                .flat_map(|cs| {
-                                    cs.stats
-                                        .iter()
-                                        .map(|s| s.as_ref().map_or(0, |s| s.games_played))
-                                })
-                                .sum::<u32>(),
-                        )
+                        -i64::from(per_role.1.iter().map(|cs| cs.total_games).sum::<u32>())
                     })
                     .collect::<Vec<_>>(),
             )
@@ -252,80 +682,84 @@ fn get_per_group_per_role_per_champ(
         .collect::<HashMap<_, _>>()
 }
 
-fn compare_players(
-    p1d: &mut HashMap<(Player, String), GroupStats>,
-    p2d: &HashMap<(Player, String), GroupStats>,
+/// Compares every column to the first one, and merges the per champion stats
+/// of all columns into one table per group and role.
+fn compare_columns(
+    data: &mut ColumnGroupStats,
+    num_columns: usize,
     per_group_per_role_per_champ: &mut GroupStatsMap,
 ) {
-    let p2 = p2d.keys().next().unwrap().0.clone();
-    for ((p1, group_name), p1_group) in p1d {
-        if let Some(p2_group) = p2d.get(&(p2.clone(), group_name.clone())) {
-            debug!("Comparing {p1} and {p2} in {group_name}");
-            p1_group.compare_to(p2_group);
-            let p1_per_role_per_champ = p1_group
-                .per_role_per_champ
-                .iter()
-                .cloned()
-                .collect::<HashMap<_, _>>();
-            let p2_per_role_per_champ = p2_group
-                .per_role_per_champ
+    let group_ids = data
+        .keys()
+        .map(|(_, group_id)| group_id.clone())
+        .sorted()
+        .dedup()
+        .collect::<Vec<_>>();
+    for group_id in group_ids {
+        if let Some(reference) = data.get(&(0, group_id.clone())).cloned() {
+            for column_index in 1..num_columns {
+                if let Some(group) = data.get_mut(&(column_index, group_id.clone())) {
+                    debug!("Comparing column {column_index} to column 0 in {group_id}");
+                    group.compare_to(&reference);
+                }
+            }
+        }
+        let per_column = (0..num_columns)
+            .map(|column_index| {
+                data.get(&(column_index, group_id.clone()))
+                    .map(|group| {
+                        group
+                            .per_role_per_champ
+                            .iter()
+                            .map(|(role, champs)| {
+                                let champs = champs
+                                    .iter()
+                                    .map(|(name, id, stats)| (id, (name, stats)))
+                                    .collect::<HashMap<_, _>>();
+                                (*role, champs)
+                            })
+                            .collect::<HashMap<_, _>>()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        let all_roles = per_column
+            .iter()
+            .flat_map(HashMap::keys)
+            .copied()
+            .sorted()
+            .dedup()
+            .collect::<Vec<_>>();
+        let group_entry = per_group_per_role_per_champ
+            .entry(group_id.clone())
+            .or_default();
+        for role in all_roles {
+            let role_entry = group_entry.entry(role).or_default();
+            let all_champs = per_column
                 .iter()
-                .cloned()
-                .collect::<HashMap<_, _>>();
-            let all_roles = p1_per_role_per_champ
-                .keys()
-                .chain(p2_per_role_per_champ.keys())
-                .copied()
+                .filter_map(|per_role| per_role.get(&role))
+                .flatten()
+                .map(|(id, (name, _stats))| (*name, *id))
                 .sorted()
                 .dedup()
                 .collect::<Vec<_>>();
-            let group_entry = per_group_per_role_per_champ
-                .entry(group_name.clone())
-                .or_default();
-            for role in all_roles {
-                let role_entry = group_entry.entry(role).or_default();
-                let p1_champ_stats = p1_per_role_per_champ.get(&role);
-                let p2_champ_stats = p2_per_role_per_champ.get(&role);
-                let p1_champ_stats = p1_champ_stats.map(|v| {
-                    v.iter()
-                        .map(|(name, id, stats)| (id, (name, stats)))
-                        .collect::<HashMap<_, _>>()
-                });
-                let p2_champ_stats = p2_champ_stats.map(|v| {
-                    v.iter()
-                        .map(|(name, id, stats)| (id, (name, stats)))
-                        .collect::<HashMap<_, _>>()
-                });
-                let all_champs = p1_champ_stats
+            for (champ_name, champ_id) in all_champs {
+                let stats = per_column
                     .iter()
-                    .chain(p2_champ_stats.iter())
-                    .flatten()
-                    .map(|(id, (name, _stats))| (*name, *id))
-                    .sorted()
-                    .dedup()
-                    .collect::<Vec<_>>();
-                for (champ_name, champ_id) in all_champs {
-                    let mut p1_stats = None;
-                    let mut p2_stats = None;
-                    if let Some(ref p1_champ_stats) = p1_champ_stats {
-                        p1_stats = p1_champ_stats
-                            .get(&champ_id)
-                            .map(|(_name, stats)| (*stats).clone());
-                    }
-                    if let Some(ref p2_champ_stats) = p2_champ_stats {
-                        p2_stats = p2_champ_stats
-                            .get(&champ_id)
-                            .map(|(_name, stats)| (*stats).clone());
-                    }
-                    role_entry.insert(
-                        champ_name.to_string(),
-                        ChampionStatsIntermediate {
-                            id: champ_id.to_string(),
-                            player1: p1_stats,
-                            player2: p2_stats,
-                        },
-                    );
-                }
+                    .map(|per_role| {
+                        per_role
+                            .get(&role)
+                            .and_then(|champs| champs.get(&champ_id))
+                            .map(|(_name, stats)| (*stats).clone())
+                    })
+                    .collect();
+                role_entry.insert(
+                    champ_name.to_string(),
+                    ChampionStatsIntermediate {
+                        id: champ_id.to_string(),
+                        stats,
+                    },
+                );
             }
         }
     }
@@ -0,0 +1,88 @@
+use crate::{
+    calculations::{invalidate_baselines, try_expression, Summary, CUSTOM_METRIC_FUNCTIONS},
+    custom_metric::CustomMetricDefinition,
+    internal_server_error, State,
+};
+use actix_web::{
+    error::{ErrorBadRequest, ErrorNotFound},
+    routes,
+    web::{self, Redirect},
+    Responder, Result as ActixResult,
+};
+use askama_actix::Template;
+use serde::Deserialize;
+
+#[derive(Template)]
+#[template(path = "custom_metrics.html")]
+struct DisplayData {
+    metrics: Vec<(String, CustomMetricDefinition)>,
+    functions: &'static [(&'static str, &'static str)],
+}
+
+#[derive(Deserialize)]
+struct CustomMetricForm {
+    name: String,
+    expression: String,
+    summary: Summary,
+    higher_is_better: bool,
+    // Checkboxes are only sent when they're checked
+    percent: Option<String>,
+}
+
+impl CustomMetricForm {
+    fn parse(&self, state: &State) -> Result<CustomMetricDefinition, String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("A metric needs a name".to_string());
+        }
+        let expression = self.expression.trim();
+        try_expression(state, expression)?;
+        Ok(CustomMetricDefinition {
+            name: name.to_string(),
+            expression: expression.to_string(),
+            summary: self.summary,
+            up_is_good: self.higher_is_better,
+            percent: self.percent.is_some(),
+        })
+    }
+}
+
+#[routes]
+#[get("/metrics")]
+pub async fn list(state: State) -> ActixResult<impl Responder> {
+    Ok(DisplayData {
+        metrics: state.custom_metrics.list(),
+        functions: CUSTOM_METRIC_FUNCTIONS,
+    }
+    .customize()
+    .insert_header(("content-type", "text/html")))
+}
+
+#[routes]
+#[post("/metrics")]
+pub async fn create(
+    state: State,
+    form: web::Form<CustomMetricForm>,
+) -> ActixResult<impl Responder> {
+    let definition = form.parse(&state).map_err(ErrorBadRequest)?;
+    let name = definition.name.clone();
+    state
+        .custom_metrics
+        .insert_new(&name, definition)
+        .map_err(internal_server_error)?;
+    invalidate_baselines(&state);
+    Ok(Redirect::to("/metrics").see_other())
+}
+
+#[routes]
+#[post("/metrics/{id}/delete")]
+pub async fn delete(state: State, path: web::Path<String>) -> ActixResult<impl Responder> {
+    let id = path.into_inner();
+    state
+        .custom_metrics
+        .remove(&id)
+        .map_err(internal_server_error)?
+        .ok_or_else(|| ErrorNotFound(format!("No custom metric with ID {id}")))?;
+    invalidate_baselines(&state);
+    Ok(Redirect::to("/metrics").see_other())
+}
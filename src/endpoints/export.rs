@@ -0,0 +1,222 @@
+use crate::{
+    calculations::{default_start, GameMetrics},
+    fetcher::{check_or_start_fetching, RedirectOrContinue},
+    internal_server_error, Player, PlayerRoleChamp, State,
+};
+use actix_web::{routes, web, Either, HttpRequest, HttpResponse, Responder, Result as ActixResult};
+use log::debug;
+
+async fn export(
+    state: State,
+    request: HttpRequest,
+    path: PlayerRoleChamp,
+    extension: &str,
+    content_type: &str,
+    serialize: fn(&[GameMetrics]) -> crate::Result<Vec<u8>>,
+) -> ActixResult<impl Responder> {
+    let (mut player, role, champion) = path.into();
+    debug!("Exporting {player} in {role:?} as {champion:?} to {extension}");
+    if let RedirectOrContinue::Redirect(redirect) = check_or_start_fetching(
+        state.clone(),
+        &player,
+        default_start(),
+        Some(request.path()),
+    )
+    .await
+    .map_err(internal_server_error)?
+    {
+        return Ok(Either::Left(redirect));
+    }
+    let metrics =
+        crate::calculations::calc_game_metrics(state, &mut player, role, champion.as_deref())
+            .await
+            .map_err(internal_server_error)?;
+    let body = serialize(&metrics).map_err(internal_server_error)?;
+    Ok(Either::Right(
+        HttpResponse::Ok()
+            .content_type(content_type)
+            .insert_header((
+                "content-disposition",
+                format!("attachment; filename=\"{}\"", file_name(&player, extension)),
+            ))
+            .body(body),
+    ))
+}
+
+fn file_name(player: &Player, extension: &str) -> String {
+    format!("{}-{}.{extension}", player.game_name, player.tag_line)
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn to_csv(metrics: &[GameMetrics]) -> crate::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    for game in metrics {
+        writer.serialize(game)?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+fn to_jsonl(metrics: &[GameMetrics]) -> crate::Result<Vec<u8>> {
+    let mut out = vec![];
+    for game in metrics {
+        serde_json::to_writer(&mut out, game)?;
+        out.push(b'\n');
+    }
+    Ok(out)
+}
+
+#[routes]
+#[get("/export/csv/{region}/{game_name}/{tag_line}")]
+#[get("/export/csv/{region}/{game_name}/{tag_line}/{role}")]
+#[get("/export/csv/{region}/{game_name}/{tag_line}/{role}/{champion}")]
+pub async fn export_csv(
+    state: State,
+    request: HttpRequest,
+    path: web::Path<PlayerRoleChamp>,
+) -> ActixResult<impl Responder> {
+    export(state, request, path.into_inner(), "csv", "text/csv", to_csv).await
+}
+
+#[routes]
+#[get("/export/jsonl/{region}/{game_name}/{tag_line}")]
+#[get("/export/jsonl/{region}/{game_name}/{tag_line}/{role}")]
+#[get("/export/jsonl/{region}/{game_name}/{tag_line}/{role}/{champion}")]
+pub async fn export_jsonl(
+    state: State,
+    request: HttpRequest,
+    path: web::Path<PlayerRoleChamp>,
+) -> ActixResult<impl Responder> {
+    export(
+        state,
+        request,
+        path.into_inner(),
+        "jsonl",
+        "application/jsonl",
+        to_jsonl,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{file_name, to_csv, to_jsonl};
+    use crate::{calculations::GameMetrics, riot_api::json::Role, LeagueRegion, Player};
+    use chrono::DateTime;
+    use test_case::test_case;
+
+    fn game(champion: &str, opponent: Option<&str>) -> GameMetrics {
+        GameMetrics {
+            match_id: "EUW1_1".to_string(),
+            date: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            patch: "14.1".to_string(),
+            champion: champion.to_string(),
+            role: Role::Middle,
+            opponent: opponent.map(str::to_string),
+            opponent_confidence: 0.5,
+            win: true,
+            duration_minutes: 30,
+            kills: 5,
+            deaths: 2,
+            assists: 7,
+            kda: 6.0,
+            cs_per_minute: 8.5,
+            gold_share: 0.25,
+            champion_damage_share: 0.3,
+            objective_damage_share: 0.1,
+            vision_share: 0.15,
+            vision_score_per_minute: 1.2,
+            solo_kills: 1,
+            solo_deaths: 0,
+            kill_participation: 0.6,
+            fights: 4,
+            fights_participated: 3,
+            fights_died_first: 1,
+            fights_won: 2,
+            cs_per_minute_at_10: Some(8.0),
+            gold_diff_at_10: Some(150),
+            cs_diff_at_10: Some(-3),
+            level_diff_at_10: Some(0.5),
+            cs_per_minute_at_15: None,
+            gold_diff_at_15: None,
+            cs_diff_at_15: None,
+            level_diff_at_15: None,
+            first_legendary_seconds: Some(840),
+            custom: vec![Some(1.0)],
+        }
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let csv = String::from_utf8(to_csv(&[game("Ahri", Some("Syndra"))]).unwrap()).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some(
+                "match_id,date,patch,champion,role,opponent,opponent_confidence,win,\
+                 duration_minutes,kills,deaths,assists,kda,cs_per_minute,gold_share,\
+                 champion_damage_share,objective_damage_share,vision_share,\
+                 vision_score_per_minute,solo_kills,solo_deaths,kill_participation,fights,\
+                 fights_participated,fights_died_first,fights_won,cs_per_minute_at_10,\
+                 gold_diff_at_10,cs_diff_at_10,level_diff_at_10,cs_per_minute_at_15,\
+                 gold_diff_at_15,cs_diff_at_15,level_diff_at_15,first_legendary_seconds"
+            )
+        );
+        assert_eq!(
+            lines.next(),
+            Some(
+                "EUW1_1,2023-11-14T22:13:20Z,14.1,Ahri,middle,Syndra,0.5,true,30,5,2,7,6.0,8.5,\
+                 0.25,0.3,0.1,0.15,1.2,1,0,0.6,4,3,1,2,8.0,150,-3,0.5,,,,,840"
+            )
+        );
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test_case("Kai'Sa", Some("Nunu & Willump"), "Kai'Sa,middle,Nunu & Willump," ; "no quoting needed")]
+    #[test_case("Ahri", None, "Ahri,middle,," ; "no opponent")]
+    #[test_case("Ahri, the Fox", Some("\"Syndra\""), "\"Ahri, the Fox\",middle,\"\"\"Syndra\"\"\"," ; "quoted")]
+    fn test_to_csv_quoting(champion: &str, opponent: Option<&str>, expected: &str) {
+        let csv = String::from_utf8(to_csv(&[game(champion, opponent)]).unwrap()).unwrap();
+        let row = csv.lines().nth(1).unwrap();
+        assert!(row.contains(expected), "{row}");
+    }
+
+    #[test]
+    fn test_to_jsonl() {
+        let jsonl = String::from_utf8(
+            to_jsonl(&[game("Ahri", Some("Syndra")), game("Lux", None)]).unwrap(),
+        )
+        .unwrap();
+        let lines = jsonl.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(jsonl.ends_with('\n'));
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["champion"], "Ahri");
+        assert_eq!(first["opponent"], "Syndra");
+        assert_eq!(first["gold_diff_at_15"], serde_json::Value::Null);
+        assert!(first.get("custom").is_none());
+        let second: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(second["champion"], "Lux");
+        assert_eq!(second["opponent"], serde_json::Value::Null);
+    }
+
+    #[test_case("Player", "EUW", "csv", "Player-EUW.csv" ; "plain")]
+    #[test_case("Some Name", "1234", "jsonl", "Some_Name-1234.jsonl" ; "space")]
+    #[test_case("a/../b\"", "x", "csv", "a_.._b_-x.csv" ; "unsafe characters")]
+    #[test_case("Ünïcödé", "KR1", "csv", "Ünïcödé-KR1.csv" ; "unicode")]
+    fn test_file_name(game_name: &str, tag_line: &str, extension: &str, expected: &str) {
+        let player = Player {
+            region: LeagueRegion::EUW,
+            game_name: game_name.to_string(),
+            tag_line: tag_line.to_string(),
+        };
+        assert_eq!(file_name(&player, extension), expected);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{fetcher::check_or_start_fetching, internal_server_error, Player, State};
+use crate::{
+    calculations::default_start, fetcher::check_or_start_fetching, internal_server_error, Player,
+    State,
+};
 use actix_web::{routes, web, Either, HttpResponse, Responder, Result as ActixResult};
 use askama_actix::Template;
 use serde::Deserialize;
@@ -36,9 +39,14 @@ pub async fn page(
     query: web::Query<Params>,
 ) -> ActixResult<impl Responder> {
     let player = path.into_inner().normalized();
-    let _ = check_or_start_fetching(state.clone(), &player, query.from.as_deref())
-        .await
-        .map_err(internal_server_error)?;
+    let _ = check_or_start_fetching(
+        state.clone(),
+        &player,
+        default_start(),
+        query.from.as_deref(),
+    )
+    .await
+    .map_err(internal_server_error)?;
 
     Ok(DisplayData { player }
         .render()
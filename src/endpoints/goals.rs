@@ -0,0 +1,87 @@
+use crate::{
+    calculations::goal_metrics,
+    goal::{Comparison, Goal},
+    internal_server_error, normalize_champion_name,
+    riot_api::json::Role,
+    Player, State, CHAMP_NAMES,
+};
+use actix_web::{
+    error::{ErrorBadRequest, ErrorNotFound},
+    routes,
+    web::{self, Redirect},
+    Responder, Result as ActixResult,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct GoalForm {
+    metric: String,
+    comparison: Comparison,
+    threshold: f64,
+    // Empty for any role, which deserializes as `Role::None`
+    role: Role,
+    champion: String,
+    start: NaiveDate,
+}
+
+impl GoalForm {
+    fn parse(&self, state: &State, player: Player) -> Result<Goal, String> {
+        if !goal_metrics(state).iter().any(|(id, _)| *id == self.metric) {
+            return Err(format!("Unknown metric {}", self.metric));
+        }
+        if !self.threshold.is_finite() {
+            return Err("The target has to be a number".to_string());
+        }
+        let champion = normalize_champion_name(&self.champion);
+        if !champion.is_empty() && !CHAMP_NAMES.contains_key(champion.as_str()) {
+            return Err(format!("Unknown champion {}", self.champion));
+        }
+        Ok(Goal {
+            player,
+            metric: self.metric.clone(),
+            comparison: self.comparison,
+            threshold: self.threshold,
+            role: (self.role != Role::None).then_some(self.role),
+            champion: (!champion.is_empty()).then_some(champion),
+            start: self.start,
+        })
+    }
+}
+
+fn stats_url(player: &Player) -> String {
+    format!(
+        "/stats/{}/{}/{}",
+        player.region, player.game_name, player.tag_line
+    )
+}
+
+#[routes]
+#[post("/goals/{region}/{game_name}/{tag_line}")]
+pub async fn create(
+    state: State,
+    path: web::Path<Player>,
+    form: web::Form<GoalForm>,
+) -> ActixResult<impl Responder> {
+    let player = path.into_inner().normalized();
+    let goal = form
+        .parse(&state, player.clone())
+        .map_err(ErrorBadRequest)?;
+    state
+        .goals
+        .insert_new(&format!("{} {}", player.game_name, goal.metric), goal)
+        .map_err(internal_server_error)?;
+    Ok(Redirect::to(stats_url(&player)).see_other())
+}
+
+#[routes]
+#[post("/goals/{id}/delete")]
+pub async fn delete(state: State, path: web::Path<String>) -> ActixResult<impl Responder> {
+    let id = path.into_inner();
+    let goal = state
+        .goals
+        .remove(&id)
+        .map_err(internal_server_error)?
+        .ok_or_else(|| ErrorNotFound(format!("No goal with ID {id}")))?;
+    Ok(Redirect::to(stats_url(&goal.player)).see_other())
+}
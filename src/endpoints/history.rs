@@ -0,0 +1,89 @@
+use crate::{
+    calculations::{default_start, HistoryFilter, HistoryRow},
+    fetcher::{check_or_start_fetching, RedirectOrContinue},
+    internal_server_error,
+    riot_api::json::Role,
+    Player, PlayerRoleChamp, State, CHAMP_NAMES,
+};
+use actix_web::{routes, web, Either, HttpRequest, Responder, Result as ActixResult};
+use askama_actix::Template;
+use log::debug;
+use strum::IntoEnumIterator;
+
+#[derive(Template)]
+#[template(path = "history.html")]
+struct DisplayData {
+    player: Player,
+    role: Option<Role>,
+    champion: Option<String>,
+    filter: HistoryFilter,
+    roles: Vec<Role>,
+    rows: Vec<HistoryRow>,
+    wins: usize,
+    winrate: f64,
+}
+
+impl DisplayData {
+    // We have to pass by ref, because that's what Askama generates
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    fn role_selected(&self, option: &&Role) -> bool {
+        self.role == Some(**option)
+    }
+    fn result_selected(&self, result: &str) -> bool {
+        self.filter
+            .result
+            .is_some_and(|r| format!("{r:?}").eq_ignore_ascii_case(result))
+    }
+}
+
+#[routes]
+#[get("/history/{region}/{game_name}/{tag_line}")]
+#[get("/history/{region}/{game_name}/{tag_line}/{role}")]
+#[get("/history/{region}/{game_name}/{tag_line}/{role}/{champion}")]
+pub async fn page(
+    state: State,
+    request: HttpRequest,
+    path: web::Path<PlayerRoleChamp>,
+    query: web::Query<HistoryFilter>,
+) -> ActixResult<impl Responder> {
+    let (mut player, role, champion) = path.into_inner().into();
+    let filter = query.into_inner();
+    debug!("Getting history for {player} in {role:?} as {champion:?} with {filter:?}");
+    let from_url = match request.query_string() {
+        "" => request.path().to_string(),
+        query_string => format!("{}?{query_string}", request.path()),
+    };
+    if let RedirectOrContinue::Redirect(redirect) =
+        check_or_start_fetching(state.clone(), &player, default_start(), Some(&from_url))
+            .await
+            .map_err(internal_server_error)?
+    {
+        return Ok(Either::Left(redirect));
+    }
+    let rows =
+        crate::calculations::calc_history(state, &mut player, role, champion.as_deref(), &filter)
+            .await
+            .map_err(internal_server_error)?;
+    let wins = rows.iter().filter(|row| row.game.win).count();
+    #[allow(clippy::cast_precision_loss)]
+    let winrate = if rows.is_empty() {
+        0.0
+    } else {
+        wins as f64 * 100.0 / rows.len() as f64
+    };
+    let champion = champion.map(|c| (*CHAMP_NAMES.get(&c).unwrap()).to_string());
+    Ok(Either::Right(
+        DisplayData {
+            player,
+            role,
+            champion,
+            filter,
+            roles: Role::iter().filter(|r| *r != Role::None).collect(),
+            rows,
+            wins,
+            winrate,
+        }
+        .customize()
+        .insert_header(("content-type", "text/html")),
+    ))
+}
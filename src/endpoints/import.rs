@@ -0,0 +1,29 @@
+use crate::{import::Imported, internal_server_error, State};
+use actix_web::{
+    error::ErrorBadRequest, routes, web, HttpResponse, Responder, Result as ActixResult,
+};
+use askama_actix::Template;
+
+#[derive(Template)]
+#[template(path = "import.html")]
+struct DisplayData {}
+
+#[routes]
+#[get("/import")]
+pub async fn page() -> ActixResult<impl Responder> {
+    Ok(DisplayData {}
+        .customize()
+        .insert_header(("content-type", "text/html")))
+}
+
+/// Takes the contents of one JSON file with matches and/or timelines.
+#[routes]
+#[post("/import")]
+pub async fn upload(state: State, body: web::Bytes) -> ActixResult<impl Responder> {
+    let contents = std::str::from_utf8(&body).map_err(ErrorBadRequest)?;
+    let imported = Imported::parse(contents).map_err(ErrorBadRequest)?;
+    imported.save().map_err(internal_server_error)?;
+    let summary = imported.summary();
+    imported.insert(&state);
+    Ok(HttpResponse::Ok().body(summary.to_string()))
+}
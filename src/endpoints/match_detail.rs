@@ -0,0 +1,177 @@
+use crate::{
+    annotation::{ExcludedGame, MatchAnnotation},
+    calculations::{
+        exclusion_reason, invalidate_baselines, match_detail, ExclusionReason, MatchDetail,
+    },
+    internal_server_error,
+    riot_api::{get_match, get_match_timeline},
+    LeagueRegion, Player, State,
+};
+use actix_web::{
+    error::ErrorNotFound,
+    routes,
+    web::{self, Redirect},
+    Responder, Result as ActixResult,
+};
+use askama_actix::Template;
+use serde::Deserialize;
+
+#[derive(Template)]
+#[template(path = "match.html")]
+struct DisplayData {
+    region: LeagueRegion,
+    game_name: String,
+    tag_line: String,
+    puuid: String,
+    detail: MatchDetail,
+    annotation: MatchAnnotation,
+    exclusion: Option<ExclusionReason>,
+    excluded_by_hand: Option<ExcludedGame>,
+}
+
+#[derive(Deserialize)]
+struct MatchPath {
+    region: LeagueRegion,
+    match_id: String,
+    puuid: String,
+}
+
+#[derive(Deserialize)]
+struct NotesForm {
+    note: String,
+    tags: String,
+}
+
+#[derive(Deserialize)]
+struct ExcludeForm {
+    reason: String,
+}
+
+#[routes]
+#[get("/match/{region}/{match_id}/{puuid}")]
+pub async fn page(state: State, path: web::Path<MatchPath>) -> ActixResult<impl Responder> {
+    let MatchPath {
+        region,
+        match_id,
+        puuid,
+    } = path.into_inner();
+    // Only used for rate limit status updates, and nobody is waiting for
+    // those on this page.
+    let placeholder = Player {
+        region,
+        game_name: String::new(),
+        tag_line: String::new(),
+    };
+    let api_region = region.into();
+    let known_match = state
+        .matches_per_puuid
+        .get(&puuid)
+        .and_then(|matches| matches.get(&match_id).cloned());
+    let m = match known_match {
+        Some(m) => m,
+        None => get_match(&state, api_region, &match_id, &placeholder)
+            .await
+            .map_err(internal_server_error)?,
+    };
+    let participant = m
+        .info
+        .participants
+        .iter()
+        .find(|p| p.puuid == puuid)
+        .ok_or_else(|| ErrorNotFound(format!("{puuid} did not play in {match_id}")))?;
+    let game_name = participant.riot_id_game_name.clone();
+    let tag_line = participant.riot_id_tagline.clone();
+    let known_timeline = state
+        .timeline_per_match
+        .get(&match_id)
+        .map(|timeline| timeline.clone());
+    let timeline = match known_timeline {
+        Some(timeline) => timeline,
+        None => get_match_timeline(&state, api_region, &match_id, &placeholder)
+            .await
+            .map_err(internal_server_error)?,
+    };
+    let id = MatchAnnotation::id(&match_id, &puuid);
+    let annotation = state.annotations.get(&id).unwrap_or_default();
+    Ok(DisplayData {
+        region,
+        game_name,
+        tag_line,
+        detail: match_detail(&m, &timeline, &puuid),
+        exclusion: exclusion_reason(&state, &m, Some(&timeline), &puuid),
+        excluded_by_hand: state.excluded_games.get(&id),
+        puuid,
+        annotation,
+    }
+    .customize()
+    .insert_header(("content-type", "text/html")))
+}
+
+#[routes]
+#[post("/match/{region}/{match_id}/{puuid}/notes")]
+pub async fn notes(
+    state: State,
+    path: web::Path<MatchPath>,
+    form: web::Form<NotesForm>,
+) -> ActixResult<impl Responder> {
+    let MatchPath {
+        region,
+        match_id,
+        puuid,
+    } = path.into_inner();
+    let id = MatchAnnotation::id(&match_id, &puuid);
+    let annotation = MatchAnnotation::parse(&form.note, &form.tags);
+    if annotation.is_empty() {
+        state
+            .annotations
+            .remove(&id)
+            .map_err(internal_server_error)?;
+    } else {
+        state
+            .annotations
+            .insert(id, annotation)
+            .map_err(internal_server_error)?;
+    }
+    Ok(Redirect::to(format!("/match/{region}/{match_id}/{puuid}")).see_other())
+}
+
+#[routes]
+#[post("/match/{region}/{match_id}/{puuid}/exclude")]
+pub async fn exclude(
+    state: State,
+    path: web::Path<MatchPath>,
+    form: web::Form<ExcludeForm>,
+) -> ActixResult<impl Responder> {
+    let MatchPath {
+        region,
+        match_id,
+        puuid,
+    } = path.into_inner();
+    state
+        .excluded_games
+        .insert(
+            MatchAnnotation::id(&match_id, &puuid),
+            ExcludedGame {
+                reason: form.reason.trim().to_string(),
+            },
+        )
+        .map_err(internal_server_error)?;
+    invalidate_baselines(&state);
+    Ok(Redirect::to(format!("/match/{region}/{match_id}/{puuid}")).see_other())
+}
+
+#[routes]
+#[post("/match/{region}/{match_id}/{puuid}/include")]
+pub async fn include(state: State, path: web::Path<MatchPath>) -> ActixResult<impl Responder> {
+    let MatchPath {
+        region,
+        match_id,
+        puuid,
+    } = path.into_inner();
+    state
+        .excluded_games
+        .remove(&MatchAnnotation::id(&match_id, &puuid))
+        .map_err(internal_server_error)?;
+    invalidate_baselines(&state);
+    Ok(Redirect::to(format!("/match/{region}/{match_id}/{puuid}")).see_other())
+}
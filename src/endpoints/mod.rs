@@ -4,9 +4,17 @@ use actix_web::{routes, Responder, Result as ActixResult};
 use askama_actix::Template;
 use strum::IntoEnumIterator;
 
+pub mod bans;
 pub mod compare;
+pub mod custom_metrics;
+pub mod export;
 pub mod fetch;
+pub mod goals;
+pub mod history;
+pub mod import;
+pub mod match_detail;
 pub mod stats;
+pub mod team;
 
 #[derive(Template)]
 #[template(path = "index.html")]
@@ -1,5 +1,8 @@
 use crate::{
-    calculations::GroupStats,
+    calculations::{
+        current_rank, default_start, goal_metrics, known_tags, rank_chart_data, GroupStats,
+        TagFilter, NUM_WEEKS,
+    },
     fetcher::{check_or_start_fetching, RedirectOrContinue},
     internal_server_error,
     riot_api::json::Role,
@@ -7,6 +10,7 @@ use crate::{
 };
 use actix_web::{routes, web, Either, HttpRequest, Responder, Result as ActixResult};
 use askama_actix::Template;
+use chrono::{NaiveDate, Utc};
 use log::debug;
 use std::cmp::Ordering;
 
@@ -17,6 +21,14 @@ struct DisplayData {
     role: Option<Role>,
     champion: Option<String>,
     groups: Vec<GroupStats>,
+    // For the new goal form
+    goal_metrics: Vec<(String, String)>,
+    today: NaiveDate,
+    tag_filter: TagFilter,
+    known_tags: Vec<String>,
+    rank: Option<String>,
+    /// JSON array of the rank snapshots, for the chart
+    rank_history: String,
 }
 
 #[routes]
@@ -27,37 +39,46 @@ pub async fn page(
     state: State,
     request: HttpRequest,
     path: web::Path<PlayerRoleChamp>,
+    query: web::Query<TagFilter>,
 ) -> ActixResult<impl Responder> {
     let (mut player, role, champion) = path.into_inner().into();
-    debug!("Getting stats for {player} in {role:?} as {champion:?}");
+    let tag_filter = query.into_inner();
+    debug!("Getting stats for {player} in {role:?} as {champion:?} with {tag_filter:?}");
+    let from_url = match request.query_string() {
+        "" => request.path().to_string(),
+        query_string => format!("{}?{query_string}", request.path()),
+    };
     if let RedirectOrContinue::Redirect(redirect) =
-        check_or_start_fetching(state.clone(), &player, Some(request.path()))
+        check_or_start_fetching(state.clone(), &player, default_start(), Some(&from_url))
             .await
             .map_err(internal_server_error)?
     {
         return Ok(Either::Left(redirect));
     }
-    let mut groups = crate::calculations::calc_stats(state, &mut player, role, champion.as_deref())
-        .await
-        .map_err(internal_server_error)?;
-    let mut previous_group = None;
-    for current_group in &mut groups {
-        if current_group.title == "Total" {
-            continue;
-        }
-        if let Some(previous_week) = previous_group {
-            current_group.compare_to(&previous_week);
-            current_group.previous_at_minute_stats = Some(previous_week.at_minute_stats.clone());
-        }
-        previous_group = Some(current_group.clone());
-    }
+    let mut groups = crate::calculations::calc_stats_for_weeks(
+        state.clone(),
+        &mut player,
+        role,
+        champion.as_deref(),
+        NUM_WEEKS,
+        &tag_filter,
+    )
+    .await
+    .map_err(internal_server_error)?;
+    crate::calculations::compare_weeks(&mut groups);
     let champion = champion.map(|c| (*CHAMP_NAMES.get(&c).unwrap()).to_string());
     Ok(Either::Right(
         DisplayData {
-            player,
             role,
             champion,
             groups,
+            goal_metrics: goal_metrics(&state),
+            today: Utc::now().date_naive(),
+            tag_filter,
+            known_tags: known_tags(&state),
+            rank: current_rank(&state, &player),
+            rank_history: rank_chart_data(&state, &player),
+            player,
         }
         .customize()
         .insert_header(("content-type", "text/html")),
@@ -0,0 +1,190 @@
+use crate::{
+    calculations::{default_start, GroupStats, RosterAggregates},
+    fetcher::{check_or_start_fetching, RedirectOrContinue},
+    internal_server_error,
+    roster::{Roster, RosterMember},
+    State,
+};
+use actix_web::{
+    error::{ErrorBadRequest, ErrorNotFound},
+    routes,
+    web::{self, Redirect},
+    Either, HttpRequest, Responder, Result as ActixResult,
+};
+use askama_actix::Template;
+use log::debug;
+use serde::Deserialize;
+
+#[derive(Template)]
+#[template(path = "team_list.html")]
+struct ListDisplayData {
+    rosters: Vec<(String, Roster)>,
+}
+
+#[derive(Template)]
+#[template(path = "team.html")]
+struct DisplayData {
+    id: String,
+    roster: Roster,
+    // The total stats for each member, in the same order as the roster, or
+    // `None` if they have no games in their role
+    member_stats: Vec<Option<GroupStats>>,
+    aggregates: RosterAggregates,
+    // For the edit form
+    members_text: String,
+}
+
+#[derive(Deserialize)]
+struct RosterForm {
+    name: String,
+    members: String,
+}
+
+impl RosterForm {
+    fn parse(&self) -> Result<Roster, String> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err("A roster needs a name".to_string());
+        }
+        let members = self
+            .members
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(RosterMember::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if members.is_empty() {
+            return Err("A roster needs at least one member".to_string());
+        }
+        Ok(Roster {
+            name: name.to_string(),
+            members,
+        })
+    }
+}
+
+fn members_text(roster: &Roster) -> String {
+    roster
+        .members
+        .iter()
+        .map(|member| {
+            let player = &member.player;
+            let mut line = format!("{} {}#{}", player.region, player.game_name, player.tag_line);
+            if let Some(role) = member.role {
+                line.push(' ');
+                line.push_str(&role.lowercase());
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[routes]
+#[get("/team")]
+pub async fn list(state: State) -> ActixResult<impl Responder> {
+    Ok(ListDisplayData {
+        rosters: state.rosters.list(),
+    }
+    .customize()
+    .insert_header(("content-type", "text/html")))
+}
+
+#[routes]
+#[post("/team")]
+pub async fn create(state: State, form: web::Form<RosterForm>) -> ActixResult<impl Responder> {
+    let roster = form.parse().map_err(ErrorBadRequest)?;
+    let name = roster.name.clone();
+    let id = state
+        .rosters
+        .insert_new(&name, roster)
+        .map_err(internal_server_error)?;
+    Ok(Redirect::to(format!("/team/{id}")).see_other())
+}
+
+#[routes]
+#[get("/team/{id}")]
+pub async fn page(
+    state: State,
+    request: HttpRequest,
+    path: web::Path<String>,
+) -> ActixResult<impl Responder> {
+    let id = path.into_inner();
+    let mut roster = state
+        .rosters
+        .get(&id)
+        .ok_or_else(|| ErrorNotFound(format!("No roster with ID {id}")))?;
+    for member in &roster.members {
+        if let RedirectOrContinue::Redirect(redirect) = check_or_start_fetching(
+            state.clone(),
+            &member.player,
+            default_start(),
+            Some(request.path()),
+        )
+        .await
+        .map_err(internal_server_error)?
+        {
+            return Ok(Either::Left(redirect));
+        }
+    }
+    let mut member_stats = vec![];
+    for member in &mut roster.members {
+        debug!("Getting stats for {} in {:?}", member.player, member.role);
+        let groups =
+            crate::calculations::calc_stats(state.clone(), &mut member.player, member.role, None)
+                .await
+                .map_err(internal_server_error)?;
+        // Total is always last
+        let total = groups.into_iter().last().unwrap();
+        member_stats.push((total.games_played > 0).then_some(total));
+    }
+    let mut players = roster
+        .members
+        .iter()
+        .map(|member| member.player.clone())
+        .collect::<Vec<_>>();
+    let aggregates = crate::calculations::calc_roster_aggregates(state.clone(), &mut players)
+        .await
+        .map_err(internal_server_error)?;
+    Ok(Either::Right(
+        DisplayData {
+            members_text: members_text(&roster),
+            id,
+            roster,
+            member_stats,
+            aggregates,
+        }
+        .customize()
+        .insert_header(("content-type", "text/html")),
+    ))
+}
+
+#[routes]
+#[post("/team/{id}")]
+pub async fn update(
+    state: State,
+    path: web::Path<String>,
+    form: web::Form<RosterForm>,
+) -> ActixResult<impl Responder> {
+    let id = path.into_inner();
+    if state.rosters.get(&id).is_none() {
+        return Err(ErrorNotFound(format!("No roster with ID {id}")));
+    }
+    let roster = form.parse().map_err(ErrorBadRequest)?;
+    state
+        .rosters
+        .insert(id.clone(), roster)
+        .map_err(internal_server_error)?;
+    Ok(Redirect::to(format!("/team/{id}")).see_other())
+}
+
+#[routes]
+#[post("/team/{id}/delete")]
+pub async fn delete(state: State, path: web::Path<String>) -> ActixResult<impl Responder> {
+    let id = path.into_inner();
+    state
+        .rosters
+        .remove(&id)
+        .map_err(internal_server_error)?
+        .ok_or_else(|| ErrorNotFound(format!("No roster with ID {id}")))?;
+    Ok(Redirect::to("/team").see_other())
+}
@@ -4,7 +4,8 @@ use actix_web_lab::{
     sse::{self, Sse},
     util::InfallibleStream,
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use reqwest::Url;
 use serde::Serialize;
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
@@ -84,6 +85,7 @@ pub enum RedirectOrContinue {
 pub async fn check_or_start_fetching(
     state: State,
     player: &Player,
+    start: DateTime<Utc>,
     from_url: Option<&str>,
 ) -> Result<RedirectOrContinue> {
     let mut broadcaster_ref = state
@@ -97,9 +99,8 @@ pub async fn check_or_start_fetching(
     ) {
         let player_clone = player.clone();
         let state = state.clone();
-        let from = Utc::now() - chrono::Duration::days(28);
         tokio::spawn(async move {
-            let status = match update_match_history(&state, &player_clone, from).await {
+            let status = match update_match_history(&state, &player_clone, start).await {
                 Ok(()) => FetchStatus::Done,
                 Err(e) => FetchStatus::Error(e.to_string()),
             };
@@ -118,17 +119,23 @@ pub async fn check_or_start_fetching(
         FetchStatus::Done => Ok(RedirectOrContinue::Continue),
         FetchStatus::Starting => unreachable!("Starting status should have been changed"),
         _ => {
-            let mut url = format!(
-                "/fetch/{region}/{game_name}/{tag_line}",
+            // Only used to get the from URL properly encoded, so the host
+            // doesn't matter.
+            let mut url = Url::parse("http://localhost/fetch/")?.join(&format!(
+                "{region}/{game_name}/{tag_line}",
                 region = player.region,
                 game_name = player.game_name,
                 tag_line = player.tag_line
-            );
+            ))?;
             if let Some(from_url) = from_url {
-                url.push_str("?from=");
-                url.push_str(from_url);
+                url.query_pairs_mut().append_pair("from", from_url);
             }
-            Ok(RedirectOrContinue::Redirect(Redirect::to(url)))
+            let mut location = url.path().to_string();
+            if let Some(query) = url.query() {
+                location.push('?');
+                location.push_str(query);
+            }
+            Ok(RedirectOrContinue::Redirect(Redirect::to(location)))
         }
     }
 }
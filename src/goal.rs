@@ -0,0 +1,45 @@
+use crate::{riot_api::json::Role, Player};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    AtLeast,
+    AtMost,
+}
+
+impl Comparison {
+    pub fn holds(self, value: f64, threshold: f64) -> bool {
+        match self {
+            Comparison::AtLeast => value >= threshold,
+            Comparison::AtMost => value <= threshold,
+        }
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Comparison::AtLeast => write!(f, "at least"),
+            Comparison::AtMost => write!(f, "at most"),
+        }
+    }
+}
+
+/// A target set with a player, like "at least 7 CS/min at 10" or "at most
+/// 5 deaths", for the games since `start`, optionally only in one role or on
+/// one champion.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Goal {
+    pub player: Player,
+    /// ID of a built-in or custom metric
+    pub metric: String,
+    pub comparison: Comparison,
+    pub threshold: f64,
+    pub role: Option<Role>,
+    /// Normalized, like in URLs
+    pub champion: Option<String>,
+    pub start: NaiveDate,
+}
@@ -0,0 +1,282 @@
+use crate::{
+    calculations::invalidate_baselines,
+    riot_api::{json, store_match_for_all},
+    store::data_dir,
+    InnerState, Result,
+};
+use log::{info, warn};
+use serde_json::Value;
+use std::{
+    fmt::{self, Display, Formatter},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Matches and timelines that were validated, along with the JSON they came
+/// from, so that can be saved as is.
+#[derive(Default)]
+pub struct Imported {
+    matches: Vec<(Value, json::Match)>,
+    timelines: Vec<(Value, json::Timeline)>,
+}
+
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub matches: usize,
+    pub timelines: usize,
+}
+
+impl Display for ImportSummary {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Imported {} matches and {} timelines",
+            self.matches, self.timelines
+        )
+    }
+}
+
+fn imports_dir() -> PathBuf {
+    data_dir().join("imports")
+}
+
+/// Match IDs end up as file names, so only allow what the API returns, like
+/// `EUW1_6989132095`.
+fn validate_match_id(match_id: &str) -> Result<()> {
+    let valid = match_id.split_once('_').is_some_and(|(platform, number)| {
+        !platform.is_empty()
+            && platform
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+            && !number.is_empty()
+            && number.chars().all(|c| c.is_ascii_digit())
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid match ID {match_id:?}").into())
+    }
+}
+
+impl Imported {
+    /// Parses a file's contents, which can be a single match or timeline, as
+    /// the API returns them, or an array of them.
+    pub fn parse(contents: &str) -> Result<Self> {
+        let mut imported = Self::default();
+        match serde_json::from_str(contents)? {
+            Value::Array(values) => {
+                for value in values {
+                    imported.add(value)?;
+                }
+            }
+            value => imported.add(value)?,
+        }
+        Ok(imported)
+    }
+
+    /// Reads a file, or all `.json` files in a directory and its
+    /// subdirectories.
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut imported = Self::default();
+        if path.is_dir() {
+            let mut entries = fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<std::io::Result<Vec<_>>>()?;
+            entries.sort();
+            for entry in entries {
+                if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "json") {
+                    imported.extend(Self::read(&entry)?);
+                }
+            }
+        } else {
+            let contents = fs::read_to_string(path)?;
+            imported
+                .extend(Self::parse(&contents).map_err(|e| format!("{}: {e}", path.display()))?);
+        }
+        Ok(imported)
+    }
+
+    fn add(&mut self, value: Value) -> Result<()> {
+        let match_id = value["metadata"]["matchId"]
+            .as_str()
+            .ok_or("Not a match or timeline: no metadata.matchId")?
+            .to_string();
+        validate_match_id(&match_id)?;
+        // Both have participants in their info, only timelines have frames
+        if value["info"]["frames"].is_array() {
+            let timeline = serde_json::from_value(value.clone())
+                .map_err(|e| format!("Invalid timeline {match_id}: {e}"))?;
+            self.timelines.push((value, timeline));
+        } else {
+            let m = serde_json::from_value(value.clone())
+                .map_err(|e| format!("Invalid match {match_id}: {e}"))?;
+            self.matches.push((value, m));
+        }
+        Ok(())
+    }
+
+    fn extend(&mut self, other: Self) {
+        self.matches.extend(other.matches);
+        self.timelines.extend(other.timelines);
+    }
+
+    /// IDs of the matches that were imported without their timeline, which
+    /// every calculation needs.
+    fn missing_timelines(&self) -> Vec<&str> {
+        self.matches
+            .iter()
+            .map(|(_, m)| m.metadata.match_id.as_str())
+            .filter(|match_id| {
+                !self
+                    .timelines
+                    .iter()
+                    .any(|(_, timeline)| timeline.metadata.match_id == *match_id)
+            })
+            .collect()
+    }
+
+    pub fn summary(&self) -> ImportSummary {
+        ImportSummary {
+            matches: self.matches.len(),
+            timelines: self.timelines.len(),
+        }
+    }
+
+    /// Writes everything to the imports directory, where `load_saved` picks
+    /// it up on the next start.
+    pub fn save(&self) -> Result<()> {
+        let dir = imports_dir();
+        fs::create_dir_all(&dir)?;
+        let files = self
+            .matches
+            .iter()
+            .map(|(value, m)| (value, &m.metadata.match_id, "match"))
+            .chain(
+                self.timelines
+                    .iter()
+                    .map(|(value, timeline)| (value, &timeline.metadata.match_id, "timeline")),
+            );
+        for (value, match_id, kind) in files {
+            fs::write(
+                dir.join(format!("{match_id}.{kind}.json")),
+                serde_json::to_string(value)?,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Adds everything to the in-memory match store, the same way fetching
+    /// from the API does. Matches without a timeline are skipped.
+    pub fn insert(self, state: &InnerState) {
+        for (_, timeline) in self.timelines {
+            state
+                .timeline_per_match
+                .insert(timeline.metadata.match_id.clone(), timeline);
+        }
+        for (_, m) in &self.matches {
+            if state.timeline_per_match.contains_key(&m.metadata.match_id) {
+                store_match_for_all(state, m);
+            } else {
+                warn!("Skipping match {} without a timeline", m.metadata.match_id);
+            }
+        }
+        invalidate_baselines(state);
+    }
+}
+
+/// Loads everything that was imported before.
+pub fn load_saved(state: &InnerState) -> Result<()> {
+    let dir = imports_dir();
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    let imported = Imported::read(&dir)?;
+    info!("{}", imported.summary());
+    imported.insert(state);
+    Ok(())
+}
+
+/// Runs the `import` subcommand: validates the given files and directories
+/// and saves them for the server and reports to use.
+pub fn run(args: &[String]) -> Result<()> {
+    if args.is_empty() {
+        return Err("Usage: lol-performance-review import <file or directory>...".into());
+    }
+    let mut imported = Imported::default();
+    for arg in args {
+        imported.extend(Imported::read(Path::new(arg))?);
+    }
+    let missing = imported.missing_timelines();
+    if !missing.is_empty() {
+        return Err(format!(
+            "No timeline for {}, import them along with their matches",
+            missing.join(", ")
+        )
+        .into());
+    }
+    imported.save()?;
+    println!("{}", imported.summary());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{validate_match_id, Imported};
+    use serde_json::Value;
+    use test_case::test_case;
+
+    const TIMELINE: &str = include_str!("../timelines.json");
+
+    fn timeline_with_id(match_id: &str) -> Value {
+        let mut value: Value = serde_json::from_str(TIMELINE).unwrap();
+        value["metadata"]["matchId"] = Value::from(match_id);
+        value
+    }
+
+    #[test]
+    fn test_parse_timeline() {
+        let imported = Imported::parse(TIMELINE).unwrap();
+        assert_eq!(imported.summary().matches, 0);
+        assert_eq!(imported.summary().timelines, 1);
+        let (_, timeline) = &imported.timelines[0];
+        assert_eq!(timeline.metadata.match_id, "EUW1_6989132095");
+        assert_eq!(timeline.info.participants.len(), 10);
+        assert!(imported.missing_timelines().is_empty());
+    }
+
+    #[test]
+    fn test_parse_array() {
+        let values = Value::Array(vec![timeline_with_id("EUW1_1"), timeline_with_id("NA1_2")]);
+        let imported = Imported::parse(&values.to_string()).unwrap();
+        assert_eq!(imported.summary().timelines, 2);
+        assert_eq!(imported.timelines[1].1.metadata.match_id, "NA1_2");
+    }
+
+    #[test_case("EUW1_6989132095", true ; "euw")]
+    #[test_case("KR_123", true ; "kr")]
+    #[test_case("../EUW1_1", false ; "parent directory")]
+    #[test_case("EUW1_1/../../x", false ; "traversal after id")]
+    #[test_case("euw1_1", false ; "lowercase")]
+    #[test_case("EUW1_", false ; "no number")]
+    #[test_case("_1", false ; "no platform")]
+    #[test_case("EUW1_1_2", false ; "two underscores")]
+    #[test_case("EUW1 1", false ; "no underscore")]
+    fn test_validate_match_id(match_id: &str, valid: bool) {
+        assert_eq!(validate_match_id(match_id).is_ok(), valid);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_match_id() {
+        let value = timeline_with_id("../../etc/passwd_1");
+        let error = Imported::parse(&value.to_string()).err().unwrap();
+        assert_eq!(error.to_string(), "Invalid match ID \"../../etc/passwd_1\"");
+    }
+
+    #[test_case("{}", "Not a match or timeline: no metadata.matchId" ; "no metadata")]
+    #[test_case(r#"{"metadata": {"matchId": "EUW1_1"}, "info": {"frames": []}}"#, "Invalid timeline EUW1_1" ; "invalid timeline")]
+    #[test_case(r#"{"metadata": {"matchId": "EUW1_1"}, "info": {}}"#, "Invalid match EUW1_1" ; "invalid match")]
+    fn test_parse_error(contents: &str, expected: &str) {
+        let error = Imported::parse(contents).err().unwrap();
+        assert!(error.to_string().starts_with(expected), "{error}");
+    }
+}
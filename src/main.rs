@@ -1,7 +1,10 @@
 use actix_files::Files;
 use actix_web::{error::ErrorInternalServerError, middleware::Logger, web, App, HttpServer};
 use dashmap::DashMap;
-use serde::Deserialize;
+use serde::{
+    de::{value::StrDeserializer, DeserializeOwned, IntoDeserializer},
+    Deserialize, Serialize,
+};
 use std::{
     collections::HashMap,
     env,
@@ -9,21 +12,33 @@ use std::{
     sync::Arc,
 };
 
+mod annotation;
 mod calculations;
+mod custom_metric;
 mod fetcher;
+mod goal;
 use fetcher::StatusBroadcaster;
 mod endpoints;
+mod import;
+mod rank;
 mod ratelimiter;
+mod report;
 mod riot_api;
+mod roster;
+mod store;
 use riot_api::json::{self, Role};
 
 type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
 type Result<T> = std::result::Result<T, Error>;
 
 include!(concat!(env!("OUT_DIR"), "/codegen-champ-names.rs"));
+include!(concat!(env!("OUT_DIR"), "/codegen-champ-names-by-id.rs"));
+include!(concat!(env!("OUT_DIR"), "/codegen-item-names.rs"));
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(Copy, Clone, Debug, Deserialize, Eq, PartialEq, Hash, strum::Display, strum::EnumIter)]
+#[derive(
+    Copy, Clone, Debug, Deserialize, Serialize, Eq, PartialEq, Hash, strum::Display, strum::EnumIter,
+)]
 enum LeagueRegion {
     BR,
     EUNE,
@@ -53,7 +68,7 @@ enum ApiRegion {
     SEA,
 }
 
-impl ApiRegion {
+impl ratelimiter::ApiHost for ApiRegion {
     fn hostname(self) -> &'static str {
         match self {
             ApiRegion::Americas => "americas.api.riotgames.com",
@@ -64,6 +79,32 @@ impl ApiRegion {
     }
 }
 
+/// Platform routes, for the APIs that are per server rather than per region,
+/// like league-v4.
+impl ratelimiter::ApiHost for LeagueRegion {
+    fn hostname(self) -> &'static str {
+        match self {
+            LeagueRegion::BR => "br1.api.riotgames.com",
+            LeagueRegion::EUNE => "eun1.api.riotgames.com",
+            LeagueRegion::EUW => "euw1.api.riotgames.com",
+            LeagueRegion::JP => "jp1.api.riotgames.com",
+            LeagueRegion::KR => "kr.api.riotgames.com",
+            LeagueRegion::LAN => "la1.api.riotgames.com",
+            LeagueRegion::LAS => "la2.api.riotgames.com",
+            LeagueRegion::ME1 => "me1.api.riotgames.com",
+            LeagueRegion::NA => "na1.api.riotgames.com",
+            LeagueRegion::OCE => "oc1.api.riotgames.com",
+            LeagueRegion::PH2 => "ph2.api.riotgames.com",
+            LeagueRegion::SG2 => "sg2.api.riotgames.com",
+            LeagueRegion::TH2 => "th2.api.riotgames.com",
+            LeagueRegion::TR => "tr1.api.riotgames.com",
+            LeagueRegion::TW2 => "tw2.api.riotgames.com",
+            LeagueRegion::RU => "ru.api.riotgames.com",
+            LeagueRegion::VN2 => "vn2.api.riotgames.com",
+        }
+    }
+}
+
 impl From<LeagueRegion> for ApiRegion {
     fn from(region: LeagueRegion) -> Self {
         match region {
@@ -86,7 +127,7 @@ impl From<LeagueRegion> for ApiRegion {
     }
 }
 
-#[derive(Debug, Deserialize, Eq, PartialEq, Hash, Clone)]
+#[derive(Debug, Deserialize, Serialize, Eq, PartialEq, Hash, Clone)]
 struct Player {
     region: LeagueRegion,
     game_name: String,
@@ -153,10 +194,31 @@ struct InnerState {
     matches_per_puuid: DashMap<String, HashMap<String, json::Match>>,
     timeline_per_match: DashMap<String, json::Timeline>,
     fetch_status_per_player: FetchStatusPerPlayer,
+    rosters: store::JsonStore<roster::Roster>,
+    custom_metrics: store::JsonStore<custom_metric::CustomMetricDefinition>,
+    goals: store::JsonStore<goal::Goal>,
+    /// Notes and tags on matches, keyed by `MatchAnnotation::id`
+    annotations: store::JsonStore<annotation::MatchAnnotation>,
+    /// Games left out of stats by hand, keyed like the annotations
+    excluded_games: store::JsonStore<annotation::ExcludedGame>,
+    /// Solo queue ranks over time, keyed by PUUID
+    rank_history: store::JsonStore<rank::RankHistory>,
+    baselines: calculations::BaselineCache,
+    /// Only use imported and already fetched data, never the API
+    offline: bool,
 }
 
 type State = web::Data<InnerState>;
 
+/// Deserializes a single string, like a path segment, into something like an
+/// enum.
+fn from_str_value<T: DeserializeOwned>(
+    value: &str,
+) -> std::result::Result<T, serde::de::value::Error> {
+    let deserializer: StrDeserializer<'_, serde::de::value::Error> = value.into_deserializer();
+    T::deserialize(deserializer)
+}
+
 fn internal_server_error<T>(err: T) -> actix_web::Error
 where
     T: fmt::Debug + fmt::Display + 'static,
@@ -165,12 +227,20 @@ where
     ErrorInternalServerError(err)
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    dotenvy::dotenv()?;
-    env_logger::init();
+// Uploaded timelines are well over the default limit.
+const IMPORT_SIZE_LIMIT: usize = 64 * 1024 * 1024;
 
-    let api_key = env::var("RIOT_API_KEY")?;
+fn offline_from_env() -> bool {
+    env::var("OFFLINE").is_ok_and(|value| value == "1" || value == "true")
+}
+
+fn new_state(offline: bool) -> Result<InnerState> {
+    let api_key = match env::var("RIOT_API_KEY") {
+        Ok(api_key) => api_key,
+        // Never used, so it doesn't have to be set
+        Err(_) if offline => String::new(),
+        Err(e) => return Err(e.into()),
+    };
     let fetch_status_per_player = Arc::new(DashMap::new());
     let client = ratelimiter::ApiClient::new(&api_key, fetch_status_per_player.clone())?;
     let state = InnerState {
@@ -178,8 +248,32 @@ async fn main() -> Result<()> {
         matches_per_puuid: DashMap::new(),
         timeline_per_match: DashMap::new(),
         fetch_status_per_player,
+        rosters: store::JsonStore::load("rosters")?,
+        custom_metrics: store::JsonStore::load("custom_metrics")?,
+        goals: store::JsonStore::load("goals")?,
+        annotations: store::JsonStore::load("annotations")?,
+        excluded_games: store::JsonStore::load("excluded_games")?,
+        rank_history: store::JsonStore::load("rank_history")?,
+        baselines: DashMap::new(),
+        offline,
     };
-    let data = web::Data::new(state);
+    import::load_saved(&state)?;
+    Ok(state)
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenvy::dotenv()?;
+    env_logger::init();
+
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    match args.first().map(String::as_str) {
+        Some("report") => return report::run(&args[1..]).await,
+        Some("import") => return import::run(&args[1..]),
+        _ => {}
+    }
+
+    let data = web::Data::new(new_state(offline_from_env())?);
     let state = data.clone();
     tokio::spawn(async move {
         let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(10));
@@ -190,17 +284,44 @@ async fn main() -> Result<()> {
             }
         }
     });
+    let state = data.clone();
+    tokio::spawn(async move { riot_api::poll_ranks(&state).await });
 
     let server = HttpServer::new(move || {
         App::new()
             .app_data(data.clone())
+            .app_data(web::PayloadConfig::new(IMPORT_SIZE_LIMIT))
             .service(Files::new("/static", "static"))
             .service(endpoints::riot_txt)
             .route("/", web::get().to(endpoints::index))
-            .service(endpoints::compare::page2)
+            .service(endpoints::bans::page)
+            .service(endpoints::compare::page)
+            .service(endpoints::compare::self_page)
+            .service(endpoints::compare::scrim_form)
+            .service(endpoints::compare::scrim_page)
+            .service(endpoints::compare::scrim_teams_page)
+            .service(endpoints::custom_metrics::list)
+            .service(endpoints::custom_metrics::create)
+            .service(endpoints::custom_metrics::delete)
             .service(endpoints::stats::page)
             .service(endpoints::fetch::page)
             .service(endpoints::fetch::events)
+            .service(endpoints::goals::create)
+            .service(endpoints::goals::delete)
+            .service(endpoints::export::export_csv)
+            .service(endpoints::export::export_jsonl)
+            .service(endpoints::history::page)
+            .service(endpoints::import::page)
+            .service(endpoints::import::upload)
+            .service(endpoints::match_detail::page)
+            .service(endpoints::match_detail::notes)
+            .service(endpoints::match_detail::exclude)
+            .service(endpoints::match_detail::include)
+            .service(endpoints::team::list)
+            .service(endpoints::team::create)
+            .service(endpoints::team::page)
+            .service(endpoints::team::update)
+            .service(endpoints::team::delete)
             .wrap(Logger::default())
     });
     let listen_address = env::var("LISTEN_ADDRESS").unwrap_or_else(|_| "127.0.0.1".to_string());
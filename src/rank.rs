@@ -0,0 +1,71 @@
+use crate::{riot_api::json::Tier, Player};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// A player's solo queue rank at one point in time.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RankSnapshot {
+    pub time: DateTime<Utc>,
+    pub tier: Tier,
+    /// "I" to "IV", always "I" in the apex tiers
+    pub division: String,
+    pub league_points: i32,
+    pub wins: i32,
+    pub losses: i32,
+}
+
+impl RankSnapshot {
+    /// The rank as a single number, 100 per division, so that LP won and lost
+    /// across divisions and tiers adds up.
+    pub fn ladder_points(&self) -> i32 {
+        if self.tier.is_apex() {
+            return Tier::Master as i32 * 400 + self.league_points;
+        }
+        let division = match self.division.as_str() {
+            "IV" => 0,
+            "III" => 1,
+            "II" => 2,
+            _ => 3,
+        };
+        self.tier as i32 * 400 + division * 100 + self.league_points
+    }
+
+    /// Whether nothing but the time is different.
+    pub fn same_rank(&self, other: &Self) -> bool {
+        self.tier == other.tier
+            && self.division == other.division
+            && self.league_points == other.league_points
+            && self.wins == other.wins
+            && self.losses == other.losses
+    }
+}
+
+impl Display for RankSnapshot {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.tier.is_apex() {
+            write!(f, "{} {} LP", self.tier, self.league_points)
+        } else {
+            write!(
+                f,
+                "{} {} {} LP",
+                self.tier, self.division, self.league_points
+            )
+        }
+    }
+}
+
+/// Every solo queue rank we've seen a player at, oldest first, with a new
+/// snapshot only when it changed. Kept in `{DATA_DIR}/rank_history.json`,
+/// keyed by PUUID.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RankHistory {
+    pub player: Player,
+    pub snapshots: Vec<RankSnapshot>,
+}
+
+impl RankHistory {
+    pub fn current(&self) -> Option<&RankSnapshot> {
+        self.snapshots.last()
+    }
+}
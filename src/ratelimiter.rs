@@ -1,4 +1,4 @@
-use crate::{ApiRegion, FetchStatusPerPlayer, Player, Result};
+use crate::{FetchStatusPerPlayer, Player, Result};
 use dashmap::DashMap;
 use governor::{DefaultDirectRateLimiter, Quota};
 use log::{debug, trace};
@@ -16,6 +16,12 @@ use tokio::time::{interval, sleep};
 //    status_code: u16,
 //}
 
+/// Where an API is served from: a regional route for accounts and matches,
+/// or a platform route for e.g. ranks.
+pub trait ApiHost: Copy {
+    fn hostname(self) -> &'static str;
+}
+
 #[derive(Clone, Debug)]
 pub struct ApiClient {
     // Reqwest client already uses an Arc internally, so we don't need to wrap it in an Arc
@@ -23,41 +29,60 @@ pub struct ApiClient {
     app_limits: [Arc<DefaultDirectRateLimiter>; 2],
     method_limits: Arc<DashMap<String, Arc<DefaultDirectRateLimiter>>>,
     fetch_status_per_player: FetchStatusPerPlayer,
+    /// Where to send requests instead of the Riot API, with the same paths,
+    /// like a local stand-in server for testing
+    base_url: Option<String>,
 }
 
 impl ApiClient {
     pub fn new(api_key: &str, fetch_status_per_player: FetchStatusPerPlayer) -> Result<Self> {
+        let first_app_limit = env::var("RATELIMIT_APP_LIMIT_1")?.parse::<u32>()?;
+        let second_app_limit = env::var("RATELIMIT_APP_LIMIT_2")?.parse::<u32>()?;
+        let first_app_duration = duration_str::parse(env::var("RATELIMIT_APP_DURATION_1")?)?;
+        let second_app_duration = duration_str::parse(env::var("RATELIMIT_APP_DURATION_2")?)?;
+        Self::with_limits(
+            api_key,
+            fetch_status_per_player,
+            [
+                (first_app_limit, first_app_duration),
+                (second_app_limit, second_app_duration),
+            ],
+            env::var("RIOT_API_BASE_URL").ok(),
+        )
+    }
+
+    /// Like `new`, but with the app rate limits, as requests per duration,
+    /// and the base URL given rather than read from the environment.
+    pub fn with_limits(
+        api_key: &str,
+        fetch_status_per_player: FetchStatusPerPlayer,
+        app_limits: [(u32, Duration); 2],
+        base_url: Option<String>,
+    ) -> Result<Self> {
         // We always put in the same API token
         let mut headers = HeaderMap::new();
         let mut api_key_value = HeaderValue::from_str(api_key)?;
         api_key_value.set_sensitive(true);
         headers.insert("X-Riot-Token", api_key_value);
         let client = Client::builder().default_headers(headers).build()?;
-        let first_app_limit = env::var("RATELIMIT_APP_LIMIT_1")?.parse::<u32>()?;
-        let second_app_limit = env::var("RATELIMIT_APP_LIMIT_2")?.parse::<u32>()?;
-        let first_app_duration = duration_str::parse(env::var("RATELIMIT_APP_DURATION_1")?)?;
-        let second_app_duration = duration_str::parse(env::var("RATELIMIT_APP_DURATION_2")?)?;
-        let first_period_per: Duration = first_app_duration / first_app_limit;
-        let second_period_per: Duration = second_app_duration / second_app_limit;
-        let first_app_rate = Arc::new(DefaultDirectRateLimiter::direct(
-            Quota::with_period(first_period_per).unwrap(),
-        ));
-        let second_app_rate = Arc::new(DefaultDirectRateLimiter::direct(
-            Quota::with_period(second_period_per).unwrap(),
-        ));
-        let app_limits = [first_app_rate, second_app_rate];
+        let app_limits = app_limits.map(|(limit, duration)| {
+            Arc::new(DefaultDirectRateLimiter::direct(
+                Quota::with_period(duration / limit).unwrap(),
+            ))
+        });
         let method_limits = Arc::new(DashMap::new());
         Ok(Self {
             http_client: client,
             app_limits,
             method_limits,
             fetch_status_per_player,
+            base_url,
         })
     }
 
     pub async fn get<T>(
         &self,
-        region: ApiRegion,
+        region: impl ApiHost,
         method: &str,
         path_params: impl IntoIterator<Item = &str> + fmt::Debug,
         player: &Player,
@@ -71,7 +96,7 @@ impl ApiClient {
 
     pub async fn get_with_query<T>(
         &self,
-        region: ApiRegion,
+        region: impl ApiHost,
         method: &str,
         path_params: impl IntoIterator<Item = &str> + fmt::Debug,
         query_params: impl IntoIterator<Item = (&str, &str)> + fmt::Debug,
@@ -81,7 +106,7 @@ impl ApiClient {
         T: for<'a> Deserialize<'a>,
     {
         debug!("GET {method} {path_params:?} {query_params:?}");
-        let mut url = make_url(region, method, path_params)?;
+        let mut url = make_url(self.base_url.as_deref(), region, method, path_params)?;
         let mut query_params = query_params.into_iter().peekable();
         if query_params.peek().is_some() {
             url.query_pairs_mut().extend_pairs(query_params);
@@ -170,11 +195,15 @@ impl ApiClient {
 }
 
 fn make_url<'a>(
-    region: ApiRegion,
+    base_url: Option<&str>,
+    region: impl ApiHost,
     method: &str,
     path_params: impl IntoIterator<Item = &'a str>,
 ) -> Result<Url> {
-    let mut url = format!("https://{hostname}{method}", hostname = region.hostname());
+    let mut url = match base_url {
+        Some(base_url) => format!("{}{method}", base_url.trim_end_matches('/')),
+        None => format!("https://{hostname}{method}", hostname = region.hostname()),
+    };
     for arg in path_params {
         url.push('/');
         url.push_str(arg);
@@ -0,0 +1,423 @@
+use crate::{
+    calculations::{self, GroupStats, NumberWithOptionalDelta},
+    from_str_value, normalize_champion_name,
+    riot_api::json::Role,
+    LeagueRegion, Player, Result, CHAMP_NAMES,
+};
+use actix_web::web;
+use askama::Template;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{cmp::Ordering, fmt::Write};
+
+const USAGE: &str = "\
+Usage: lol-performance-review report <REGION> <game name#tag> [options]
+
+Options:
+    --role <role>           Only games in this role (top, jungle, mid, bot,
+                            support)
+    --champion <champion>   Only games on this champion
+    --weeks <weeks>         How many weeks back to go (default: 4)
+    --format <format>       html, md or json (default: html)
+    --output <file>         Where to write the report (default: based on the
+                            player name, date and format)
+    --offline               Only use imported data, without calling the API";
+
+#[derive(Clone, Copy, Debug)]
+enum Format {
+    Html,
+    Markdown,
+    Json,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Html => "html",
+            Format::Markdown => "md",
+            Format::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Args {
+    player: Player,
+    role: Option<Role>,
+    champion: Option<String>,
+    weeks: i64,
+    format: Format,
+    output: Option<String>,
+    offline: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<Args> {
+    let mut positional = vec![];
+    let mut role = None;
+    let mut champion = None;
+    let mut weeks = calculations::NUM_WEEKS;
+    let mut format = Format::Html;
+    let mut output = None;
+    let mut offline = crate::offline_from_env();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            positional.push(arg.as_str());
+            continue;
+        }
+        if arg == "--offline" {
+            offline = true;
+            continue;
+        }
+        let value = args.next().ok_or_else(|| format!("{arg} needs a value"))?;
+        match arg.as_str() {
+            "--role" => {
+                // Allow the names people actually use
+                let value = match value.to_lowercase().as_str() {
+                    "mid" => "middle".to_string(),
+                    "bot" | "adc" => "bottom".to_string(),
+                    other => other.to_string(),
+                };
+                role = Some(from_str_value(&value)?);
+            }
+            "--champion" => {
+                let normalized = normalize_champion_name(value);
+                if !CHAMP_NAMES.contains_key(&normalized) {
+                    return Err(format!("Unknown champion {value}").into());
+                }
+                champion = Some(normalized);
+            }
+            "--weeks" => {
+                weeks = value.parse()?;
+                if weeks < 1 {
+                    return Err("--weeks needs to be at least 1".into());
+                }
+            }
+            "--format" => {
+                format = match value.as_str() {
+                    "html" => Format::Html,
+                    "md" | "markdown" => Format::Markdown,
+                    "json" => Format::Json,
+                    _ => return Err(format!("Unknown format {value}").into()),
+                }
+            }
+            "--output" => output = Some(value.clone()),
+            _ => return Err(format!("Unknown option {arg}").into()),
+        }
+    }
+    let [region, riot_id] = positional[..] else {
+        return Err("Expected a region and a game name#tag".into());
+    };
+    let (game_name, tag_line) = riot_id
+        .rsplit_once('#')
+        .ok_or_else(|| format!("{riot_id} is not a game name#tag"))?;
+    let player = Player {
+        region: from_str_value::<LeagueRegion>(&region.to_uppercase())?,
+        game_name: game_name.to_string(),
+        tag_line: tag_line.to_string(),
+    }
+    .normalized();
+    Ok(Args {
+        player,
+        role,
+        champion,
+        weeks,
+        format,
+        output,
+        offline,
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct ReportMetric {
+    name: String,
+    #[serde(flatten)]
+    value: NumberWithOptionalDelta,
+}
+
+impl ReportMetric {
+    fn delta_class(&self) -> &'static str {
+        match self.value.has_visible_diff() {
+            Ordering::Greater => "positive",
+            Ordering::Less => "negative",
+            Ordering::Equal => "",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ReportGroup {
+    title: String,
+    wins: u32,
+    losses: u32,
+    small_sample: bool,
+    metrics: Vec<ReportMetric>,
+}
+
+impl From<&GroupStats> for ReportGroup {
+    fn from(group: &GroupStats) -> Self {
+        let metrics = group
+            .shown_metrics()
+            .map(|metric| ReportMetric {
+                name: metric.name.clone(),
+                value: metric.value.clone(),
+            })
+            .collect();
+        Self {
+            title: group.title.clone(),
+            wins: group.wins,
+            losses: group.losses,
+            small_sample: group.is_small_sample(),
+            metrics,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Template)]
+#[template(path = "report.html")]
+struct Report {
+    player: String,
+    role: Option<Role>,
+    champion: Option<String>,
+    weeks: i64,
+    generated: DateTime<Utc>,
+    groups: Vec<ReportGroup>,
+}
+
+impl Report {
+    fn subject(&self) -> String {
+        let mut subject = self.player.clone();
+        if let Some(role) = self.role {
+            write!(subject, " in {role}").unwrap();
+        }
+        if let Some(champion) = &self.champion {
+            write!(subject, " as {champion}").unwrap();
+        }
+        subject
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        writeln!(out, "# Performance review for {}", self.subject()).unwrap();
+        writeln!(
+            out,
+            "\nLast {} weeks, generated {}.",
+            self.weeks,
+            self.generated.format("%Y-%m-%d %H:%M UTC")
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "\nChanges marked with * are unlikely to be chance, given the games behind them."
+        )
+        .unwrap();
+        for group in &self.groups {
+            writeln!(
+                out,
+                "\n## {}\n\n{} wins, {} losses",
+                group.title, group.wins, group.losses
+            )
+            .unwrap();
+            if group.small_sample {
+                writeln!(
+                    out,
+                    "\nOnly a few games, so a few more can change these numbers a lot."
+                )
+                .unwrap();
+            }
+            writeln!(out, "\n| Metric | Value | Change |\n|---|---:|---:|").unwrap();
+            for metric in &group.metrics {
+                let delta = metric
+                    .value
+                    .delta
+                    .map(|delta| {
+                        let marker = if metric.value.significant { " *" } else { "" };
+                        format!("{delta:+.1}{marker}")
+                    })
+                    .unwrap_or_default();
+                writeln!(
+                    out,
+                    "| {} | {:.1} | {delta} |",
+                    metric.name, metric.value.number
+                )
+                .unwrap();
+            }
+        }
+        out
+    }
+}
+
+/// Runs the `report` subcommand: fetches the player's games, calculates their
+/// stats and writes them to a file, without starting the web server.
+pub async fn run(args: &[String]) -> Result<()> {
+    let Args {
+        mut player,
+        role,
+        champion,
+        weeks,
+        format,
+        output,
+        offline,
+    } = parse_args(args).map_err(|e| format!("{e}\n\n{USAGE}"))?;
+    let state = web::Data::new(crate::new_state(offline)?);
+    let mut groups = calculations::calc_stats_for_weeks(
+        state,
+        &mut player,
+        role,
+        champion.as_deref(),
+        weeks,
+        &calculations::TagFilter::default(),
+    )
+    .await?;
+    calculations::compare_weeks(&mut groups);
+    let report = Report {
+        player: format!("{}#{}", player.game_name, player.tag_line),
+        role,
+        champion: champion.map(|c| (*CHAMP_NAMES.get(&c).unwrap()).to_string()),
+        weeks,
+        generated: Utc::now(),
+        groups: groups.iter().map(ReportGroup::from).collect(),
+    };
+    let contents = match format {
+        Format::Html => report.render()?,
+        Format::Markdown => report.to_markdown(),
+        Format::Json => serde_json::to_string_pretty(&report)?,
+    };
+    let output = output.unwrap_or_else(|| {
+        format!(
+            "{}-{}-{}.{}",
+            player.game_name,
+            player.tag_line,
+            report.generated.format("%Y-%m-%d"),
+            format.extension()
+        )
+    });
+    std::fs::write(&output, contents)?;
+    println!("Wrote {output}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_args, Report, ReportGroup, ReportMetric};
+    use crate::{calculations::NumberWithOptionalDelta, riot_api::json::Role, LeagueRegion};
+    use chrono::DateTime;
+    use test_case::test_case;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test_case(&["euw", "Some Name#euw"], 4, "html", None ; "defaults")]
+    #[test_case(&["--weeks", "8", "euw", "Some Name#euw"], 8, "html", None ; "weeks")]
+    #[test_case(&["euw", "Some Name#euw", "--format", "markdown", "--role", "mid"], 4, "md", Some(Role::Middle) ; "format and role")]
+    #[test_case(&["euw", "Some Name#euw", "--format", "json", "--role", "adc"], 4, "json", Some(Role::Bottom) ; "json and role alias")]
+    fn test_parse_args(input: &[&str], weeks: i64, extension: &str, role: Option<Role>) {
+        let parsed = parse_args(&args(input)).unwrap();
+        assert_eq!(parsed.player.region, LeagueRegion::EUW);
+        assert_eq!(parsed.player.game_name, "some name");
+        assert_eq!(parsed.player.tag_line, "EUW");
+        assert_eq!(parsed.weeks, weeks);
+        assert_eq!(parsed.format.extension(), extension);
+        assert_eq!(parsed.role, role);
+        assert_eq!(parsed.champion, None);
+        assert_eq!(parsed.output, None);
+    }
+
+    #[test]
+    fn test_parse_args_champion_and_output() {
+        let parsed = parse_args(&args(&[
+            "kr",
+            "Name#With#Hash#kr1",
+            "--champion",
+            "Lee Sin",
+            "--output",
+            "out.md",
+        ]))
+        .unwrap();
+        assert_eq!(parsed.player.game_name, "name#with#hash");
+        assert_eq!(parsed.player.tag_line, "KR1");
+        assert_eq!(parsed.champion.as_deref(), Some("leesin"));
+        assert_eq!(parsed.output.as_deref(), Some("out.md"));
+    }
+
+    #[test_case(&["euw", "Some Name#euw", "--format", "pdf"], "Unknown format pdf" ; "bad format")]
+    #[test_case(&["euw", "Some Name"], "Some Name is not a game name#tag" ; "missing tag")]
+    #[test_case(&["euw"], "Expected a region and a game name#tag" ; "missing name")]
+    #[test_case(&["euw", "Some Name#euw", "--weeks", "0"], "--weeks needs to be at least 1" ; "zero weeks")]
+    #[test_case(&["euw", "Some Name#euw", "--weeks"], "--weeks needs a value" ; "missing value")]
+    #[test_case(&["euw", "Some Name#euw", "--champion", "Nobody"], "Unknown champion Nobody" ; "unknown champion")]
+    #[test_case(&["euw", "Some Name#euw", "--verbose", "yes"], "Unknown option --verbose" ; "unknown option")]
+    fn test_parse_args_error(input: &[&str], expected: &str) {
+        assert_eq!(parse_args(&args(input)).unwrap_err().to_string(), expected);
+    }
+
+    fn metric(name: &str, number: f64, delta: Option<f64>, significant: bool) -> ReportMetric {
+        let mut value = NumberWithOptionalDelta::from(number);
+        value.delta = delta;
+        value.significant = significant;
+        ReportMetric {
+            name: name.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let report = Report {
+            player: "some name#EUW".to_string(),
+            role: Some(Role::Middle),
+            champion: Some("Ahri".to_string()),
+            weeks: 4,
+            generated: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            groups: vec![
+                ReportGroup {
+                    title: "This week".to_string(),
+                    wins: 3,
+                    losses: 2,
+                    small_sample: true,
+                    metrics: vec![
+                        metric("KDA", 3.25, Some(1.04), true),
+                        metric("CS/min", 7.0, Some(-0.5), false),
+                    ],
+                },
+                ReportGroup {
+                    title: "Last week".to_string(),
+                    wins: 10,
+                    losses: 8,
+                    small_sample: false,
+                    metrics: vec![metric("KDA", 2.2, None, false)],
+                },
+            ],
+        };
+        assert_eq!(
+            report.to_markdown(),
+            "\
+# Performance review for some name#EUW in Middle as Ahri
+
+Last 4 weeks, generated 2023-11-14 22:13 UTC.
+
+Changes marked with * are unlikely to be chance, given the games behind them.
+
+## This week
+
+3 wins, 2 losses
+
+Only a few games, so a few more can change these numbers a lot.
+
+| Metric | Value | Change |
+|---|---:|---:|
+| KDA | 3.3 | +1.0 * |
+| CS/min | 7.0 | -0.5 |
+
+## Last week
+
+10 wins, 8 losses
+
+| Metric | Value | Change |
+|---|---:|---:|
+| KDA | 2.2 |  |
+"
+        );
+    }
+}
@@ -47,7 +47,7 @@ pub struct Objective {
     pub kills: i32,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Missions {
     #[serde(rename = "playerScore0")]
@@ -76,14 +76,14 @@ pub struct Missions {
     pub player11: Option<i32>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Perks {
     pub stat_perks: PerkStats,
     pub styles: Vec<PerkStyle>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PerkStats {
     pub defense: i32,
@@ -129,6 +129,7 @@ pub struct PerkStyle {
     Clone,
     Copy,
     Debug,
+    Default,
     Eq,
     Hash,
     Ord,
@@ -152,6 +153,7 @@ pub enum Role {
     #[serde(alias = "UTILITY")]
     Support,
     #[serde(alias = "")]
+    #[default]
     None,
 }
 
@@ -162,7 +164,7 @@ impl Role {
 }
 
 #[allow(clippy::struct_excessive_bools)]
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[serde_as]
 pub struct Participant {
@@ -339,6 +341,17 @@ pub struct Info {
     pub tournament_code: Option<String>,
 }
 
+impl Info {
+    /// The patch the game was played on, e.g. `14.20` for version `14.20.628.3435`.
+    pub fn patch(&self) -> String {
+        self.game_version
+            .split('.')
+            .take(2)
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
 impl From<InfoShadow> for Info {
     fn from(info: InfoShadow) -> Self {
         let game_duration = if info.game_end_timestamp.is_some() {
@@ -638,3 +651,41 @@ pub struct Timeline {
     pub metadata: Metadata,
     pub info: TimelineInfo,
 }
+
+/// Ranked tiers, lowest first.
+#[derive(
+    Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Deserialize, Serialize, strum::Display,
+)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Tier {
+    Iron,
+    Bronze,
+    Silver,
+    Gold,
+    Platinum,
+    Emerald,
+    Diamond,
+    Master,
+    Grandmaster,
+    Challenger,
+}
+
+impl Tier {
+    /// Master and up, which have no divisions and share one ladder
+    pub fn is_apex(self) -> bool {
+        self >= Tier::Master
+    }
+}
+
+/// A player's standing in one ranked queue, from league-v4.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeagueEntry {
+    pub queue_type: String,
+    pub tier: Tier,
+    /// The division, "I" to "IV"
+    pub rank: String,
+    pub league_points: i32,
+    pub wins: i32,
+    pub losses: i32,
+}
@@ -1,11 +1,21 @@
-use crate::{fetcher::FetchStatus, ApiRegion, Player, Result, State};
+use crate::{
+    calculations::invalidate_baselines,
+    fetcher::FetchStatus,
+    rank::{RankHistory, RankSnapshot},
+    ApiRegion, InnerState, LeagueRegion, Player, Result, State,
+};
 use cached::proc_macro::{cached, io_cached};
 use chrono::{DateTime, Utc};
-use log::info;
+use log::{info, warn};
 use serde_json::Value;
+use std::time::Duration;
 
 pub mod json;
 
+// The queue the match histories are for
+const SOLO_QUEUE: &str = "RANKED_SOLO_5x5";
+const RANK_POLL_INTERVAL: Duration = Duration::from_hours(1);
+
 #[io_cached(
     disk = true,
     disk_dir = "cache",
@@ -26,13 +36,55 @@ pub async fn get_puuid_raw(state: &State, player: &Player) -> Result<Value> {
         .await
 }
 
+/// Looks the player up in the matches we already have, for offline mode.
+/// Returns the PUUID and the name as it was in their most recent match.
+fn find_puuid_offline(state: &State, player: &Player) -> Result<(String, String, String)> {
+    state
+        .matches_per_puuid
+        .iter()
+        .flat_map(|entry| {
+            let puuid = entry.key().clone();
+            entry
+                .value()
+                .values()
+                .filter_map(|m| {
+                    let participant = m.info.participants.iter().find(|p| p.puuid == puuid)?;
+                    (participant.riot_id_game_name.to_lowercase()
+                        == player.game_name.to_lowercase()
+                        && participant.riot_id_tagline.to_uppercase()
+                            == player.tag_line.to_uppercase())
+                    .then(|| {
+                        (
+                            m.info.game_start_timestamp,
+                            participant.riot_id_game_name.clone(),
+                            participant.riot_id_tagline.clone(),
+                        )
+                    })
+                })
+                .map(|(start, game_name, tag_line)| (start, puuid.clone(), game_name, tag_line))
+                .collect::<Vec<_>>()
+        })
+        .max_by_key(|(start, ..)| *start)
+        .map(|(_, puuid, game_name, tag_line)| (puuid, game_name, tag_line))
+        .ok_or_else(|| format!("No imported matches for {player}").into())
+}
+
 pub async fn get_puuid(state: &State, player: &Player) -> Result<String> {
+    if state.offline {
+        return Ok(find_puuid_offline(state, player)?.0);
+    }
     let json = get_puuid_raw(state, player).await?;
     let puuid = json["puuid"].as_str().ok_or("No PUUID")?;
     Ok(puuid.to_string())
 }
 
 pub async fn get_puuid_and_canonical_name(state: &State, player: &mut Player) -> Result<String> {
+    if state.offline {
+        let (puuid, game_name, tag_line) = find_puuid_offline(state, player)?;
+        player.game_name = game_name;
+        player.tag_line = tag_line;
+        return Ok(puuid);
+    }
     let json = get_puuid_raw(state, player).await?;
     let puuid = json["puuid"].as_str().ok_or("No PUUID")?;
     let game_name = json["gameName"].as_str().ok_or("No game name")?;
@@ -115,11 +167,163 @@ pub async fn get_match_history(
         .await
 }
 
+#[cached(
+    result = true,
+    time = 300, // 5 minutes
+    key = "String",
+    convert = r#"{ format!("{region}#{tournament_code}") }"#,
+)]
+pub async fn get_match_ids_by_tournament_code(
+    state: &State,
+    region: ApiRegion,
+    tournament_code: &str,
+    player: &Player,
+) -> Result<Vec<String>> {
+    state
+        .client
+        .get::<Vec<String>>(
+            region,
+            "/lol/match/v5/matches/by-tournament-code",
+            [tournament_code, "ids"],
+            player,
+        )
+        .await
+}
+
+#[cached(
+    result = true,
+    time = 600, // 10 minutes
+    key = "String",
+    convert = r#"{ format!("{region}#{puuid}") }"#,
+)]
+pub async fn get_league_entries(
+    state: &State,
+    region: LeagueRegion,
+    puuid: &str,
+    player: &Player,
+) -> Result<Vec<json::LeagueEntry>> {
+    state
+        .client
+        .get::<Vec<json::LeagueEntry>>(region, "/lol/league/v4/entries/by-puuid", [puuid], player)
+        .await
+}
+
+/// Looks up the player's solo queue rank, and adds it to their rank history
+/// if it changed. Nothing is added while they're unranked. A rank looked up in
+/// the last 10 minutes is reused rather than asked for again.
+pub async fn update_rank(state: &State, player: &Player, puuid: &str) -> Result<()> {
+    if state.offline {
+        return Ok(());
+    }
+    let entries = get_league_entries(state, player.region, puuid, player).await?;
+    let Some(entry) = entries
+        .into_iter()
+        .find(|entry| entry.queue_type == SOLO_QUEUE)
+    else {
+        return Ok(());
+    };
+    let snapshot = RankSnapshot {
+        time: Utc::now(),
+        tier: entry.tier,
+        division: entry.rank,
+        league_points: entry.league_points,
+        wins: entry.wins,
+        losses: entry.losses,
+    };
+    let mut history = state
+        .rank_history
+        .get(puuid)
+        .unwrap_or_else(|| RankHistory {
+            player: player.clone(),
+            snapshots: vec![],
+        });
+    if history
+        .current()
+        .is_some_and(|current| current.same_rank(&snapshot))
+    {
+        return Ok(());
+    }
+    // They may have changed their name since
+    history.player = player.clone().normalized();
+    history.snapshots.push(snapshot);
+    state.rank_history.insert(puuid.to_string(), history)
+}
+
+/// Keeps the rank of everyone we've looked up before up to date, so their
+/// history also has the changes between visits.
+pub async fn poll_ranks(state: &State) {
+    if state.offline {
+        return;
+    }
+    let mut interval = tokio::time::interval(RANK_POLL_INTERVAL);
+    loop {
+        interval.tick().await;
+        for (puuid, history) in state.rank_history.list() {
+            if let Err(e) = update_rank(state, &history.player, &puuid).await {
+                warn!("Couldn't update the rank of {}: {e}", history.player);
+            }
+        }
+    }
+}
+
+/// Finds a match we already have, fetched for anyone or imported.
+pub fn find_stored_match(state: &State, match_id: &str) -> Option<json::Match> {
+    state
+        .matches_per_puuid
+        .iter()
+        .find_map(|matches| matches.get(match_id).cloned())
+}
+
+/// Stores a match for all of its participants, not just the one we fetched
+/// it for.
+pub fn store_match_for_all(state: &InnerState, m: &json::Match) {
+    for participant in &m.info.participants {
+        state
+            .matches_per_puuid
+            .entry(participant.puuid.clone())
+            .or_default()
+            .insert(m.metadata.match_id.clone(), m.clone());
+    }
+    invalidate_baselines(state);
+}
+
+/// Fetches specific matches and their timelines, like custom games that
+/// don't show up in match histories, and stores them for every participant.
+pub async fn fetch_matches_by_id(
+    state: &State,
+    region: ApiRegion,
+    match_ids: &[String],
+    player: &Player,
+) -> Result<Vec<json::Match>> {
+    let mut matches = vec![];
+    for match_id in match_ids {
+        let m = match find_stored_match(state, match_id) {
+            Some(m) => m,
+            None if state.offline => return Err(format!("{match_id} was not imported").into()),
+            None => get_match(state, region, match_id, player).await?,
+        };
+        if !state.timeline_per_match.contains_key(match_id) {
+            if state.offline {
+                return Err(format!("The timeline for {match_id} was not imported").into());
+            }
+            let timeline = get_match_timeline(state, region, match_id, player).await?;
+            state.timeline_per_match.insert(match_id.clone(), timeline);
+        }
+        store_match_for_all(state, &m);
+        matches.push(m);
+    }
+    Ok(matches)
+}
+
 pub async fn update_match_history(
     state: &State,
     player: &Player,
     start: DateTime<Utc>,
 ) -> Result<()> {
+    if state.offline {
+        info!("Offline, not updating match history for {player}");
+        return Ok(());
+    }
     info!("Updating match history for {player}");
     let puuid = get_puuid(state, player).await?;
     let region = player.region.into();
@@ -139,19 +343,35 @@ pub async fn update_match_history(
             None => return Err("No matches found".into()),
         };
     }
-    let mut matches = state.matches_per_puuid.entry(puuid).or_default();
+    // Not worth failing the whole update over
+    if let Err(e) = update_rank(state, player, &puuid).await {
+        warn!("Couldn't update the rank of {player}: {e}");
+    }
     for (index, match_id) in match_ids.iter().enumerate() {
-        if !matches.contains_key(match_id) {
-            matches.insert(
-                match_id.to_string(),
-                get_match(state, region, match_id, player).await?,
-            );
-        }
+        // Holding on to an entry while waiting for the API would block
+        // everyone else using the same shard of the map
+        let is_stored = state
+            .matches_per_puuid
+            .get(&puuid)
+            .is_some_and(|matches| matches.contains_key(match_id));
+        let new_match = if is_stored {
+            None
+        } else {
+            Some(get_match(state, region, match_id, player).await?)
+        };
         if !state.timeline_per_match.contains_key(match_id) {
-            state.timeline_per_match.insert(
-                match_id.to_string(),
-                get_match_timeline(state, region, match_id, player).await?,
-            );
+            let timeline = get_match_timeline(state, region, match_id, player).await?;
+            state.timeline_per_match.insert(match_id.clone(), timeline);
+            invalidate_baselines(state);
+        }
+        // Only once its timeline is there, since every calculation needs it
+        if let Some(m) = new_match {
+            state
+                .matches_per_puuid
+                .entry(puuid.clone())
+                .or_default()
+                .insert(match_id.clone(), m);
+            invalidate_baselines(state);
         }
         if let Some(mut broadcaster) = state.fetch_status_per_player.get_mut(player) {
             broadcaster
@@ -163,3 +383,171 @@ pub async fn update_match_history(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::update_rank;
+    use crate::{
+        rank::{RankHistory, RankSnapshot},
+        ratelimiter::ApiClient,
+        riot_api::json::Tier,
+        store::JsonStore,
+        InnerState, LeagueRegion, Player, State,
+    };
+    use actix_web::web;
+    use chrono::{TimeDelta, Utc};
+    use dashmap::DashMap;
+    use std::{env, fs, sync::Arc, time::Duration};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    const GOLD_II: &str = r#"{
+        "queueType": "RANKED_SOLO_5x5", "tier": "GOLD", "rank": "II",
+        "leaguePoints": 50, "wins": 20, "losses": 18
+    }"#;
+    const FLEX: &str = r#"{
+        "queueType": "RANKED_FLEX_SR", "tier": "SILVER", "rank": "I",
+        "leaguePoints": 10, "wins": 3, "losses": 4
+    }"#;
+
+    /// Stands in for league-v4: the PUUID `unranked` is only ranked in flex,
+    /// everyone else is Gold II in solo queue.
+    async fn stub_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![0; 4096];
+                let read = stream.read(&mut request).await.unwrap();
+                let request = String::from_utf8_lossy(&request[..read]);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                assert!(
+                    path.starts_with("/lol/league/v4/entries/by-puuid/"),
+                    "{path}"
+                );
+                let body = if path.ends_with("/unranked") {
+                    format!("[{FLEX}]")
+                } else {
+                    format!("[{FLEX}, {GOLD_II}]")
+                };
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\n\
+                     content-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        format!("http://{address}")
+    }
+
+    async fn test_state(test: &str) -> State {
+        let dir = env::temp_dir().join(format!(
+            "lol-performance-review-{}-{test}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        let fetch_status_per_player = Arc::new(DashMap::new());
+        let client = ApiClient::with_limits(
+            "test",
+            fetch_status_per_player.clone(),
+            [(100, Duration::from_secs(1)), (100, Duration::from_secs(1))],
+            Some(stub_server().await),
+        )
+        .unwrap();
+        web::Data::new(InnerState {
+            client,
+            matches_per_puuid: DashMap::new(),
+            timeline_per_match: DashMap::new(),
+            fetch_status_per_player,
+            rosters: JsonStore::load_path(dir.join("rosters.json")).unwrap(),
+            custom_metrics: JsonStore::load_path(dir.join("custom_metrics.json")).unwrap(),
+            goals: JsonStore::load_path(dir.join("goals.json")).unwrap(),
+            annotations: JsonStore::load_path(dir.join("annotations.json")).unwrap(),
+            excluded_games: JsonStore::load_path(dir.join("excluded_games.json")).unwrap(),
+            rank_history: JsonStore::load_path(dir.join("rank_history.json")).unwrap(),
+            baselines: DashMap::new(),
+            offline: false,
+        })
+    }
+
+    fn player() -> Player {
+        Player {
+            region: LeagueRegion::EUW,
+            game_name: "some name".to_string(),
+            tag_line: "EUW".to_string(),
+        }
+    }
+
+    fn snapshot(tier: Tier, division: &str, league_points: i32) -> RankSnapshot {
+        RankSnapshot {
+            time: Utc::now() - TimeDelta::days(1),
+            tier,
+            division: division.to_string(),
+            league_points,
+            wins: 20,
+            losses: 18,
+        }
+    }
+
+    fn ranks(state: &State, puuid: &str) -> Vec<String> {
+        state
+            .rank_history
+            .get(puuid)
+            .map(|history| history.snapshots.iter().map(ToString::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_update_rank_unranked() {
+        let state = test_state("unranked").await;
+        update_rank(&state, &player(), "unranked").await.unwrap();
+        assert!(state.rank_history.get("unranked").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_update_rank_first_snapshot() {
+        let state = test_state("first").await;
+        update_rank(&state, &player(), "first").await.unwrap();
+        assert_eq!(ranks(&state, "first"), ["Gold II 50 LP"]);
+        // Looked up again within 10 minutes, and unchanged either way
+        update_rank(&state, &player(), "first").await.unwrap();
+        assert_eq!(ranks(&state, "first"), ["Gold II 50 LP"]);
+    }
+
+    #[tokio::test]
+    async fn test_update_rank_unchanged() {
+        let state = test_state("unchanged").await;
+        let history = RankHistory {
+            player: player(),
+            snapshots: vec![snapshot(Tier::Gold, "II", 50)],
+        };
+        state
+            .rank_history
+            .insert("unchanged".to_string(), history)
+            .unwrap();
+        update_rank(&state, &player(), "unchanged").await.unwrap();
+        assert_eq!(ranks(&state, "unchanged"), ["Gold II 50 LP"]);
+    }
+
+    #[tokio::test]
+    async fn test_update_rank_changed() {
+        let state = test_state("changed").await;
+        let history = RankHistory {
+            player: player(),
+            snapshots: vec![snapshot(Tier::Gold, "III", 80)],
+        };
+        state
+            .rank_history
+            .insert("changed".to_string(), history)
+            .unwrap();
+        update_rank(&state, &player(), "changed").await.unwrap();
+        assert_eq!(
+            ranks(&state, "changed"),
+            ["Gold III 80 LP", "Gold II 50 LP"]
+        );
+    }
+}
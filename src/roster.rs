@@ -0,0 +1,85 @@
+use crate::{from_str_value, riot_api::json::Role, LeagueRegion, Player};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RosterMember {
+    pub player: Player,
+    // Subs don't have a fixed role
+    pub role: Option<Role>,
+}
+
+impl RosterMember {
+    /// Parses `{region} {game_name}#{tag_line} [{role}]`, e.g.
+    /// `EUW Some Name#1234 jungle`. Game names can contain spaces, tag lines
+    /// can't.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (region, rest) = line
+            .split_once(' ')
+            .ok_or_else(|| format!("Expected a region and a Riot ID: {line}"))?;
+        let region = from_str_value::<LeagueRegion>(&region.to_uppercase())
+            .map_err(|e| format!("Invalid region {region}: {e}"))?;
+        let (game_name, rest) = rest
+            .rsplit_once('#')
+            .ok_or_else(|| format!("Expected a Riot ID with a #: {line}"))?;
+        let mut rest = rest.split_whitespace();
+        let tag_line = rest
+            .next()
+            .ok_or_else(|| format!("Missing tag line: {line}"))?;
+        let role = rest
+            .next()
+            .map(|role| {
+                from_str_value::<Role>(&role.to_lowercase())
+                    .map_err(|e| format!("Invalid role {role}: {e}"))
+            })
+            .transpose()?;
+        Ok(Self {
+            player: Player {
+                region,
+                game_name: game_name.trim().to_string(),
+                tag_line: tag_line.to_string(),
+            }
+            .normalized(),
+            role,
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Roster {
+    pub name: String,
+    pub members: Vec<RosterMember>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RosterMember;
+    use crate::{riot_api::json::Role, LeagueRegion};
+    use test_case::test_case;
+
+    #[test_case("EUW Some Name#1234 jungle", LeagueRegion::EUW, "some name", "1234", Some(Role::Jungle) ; "with role")]
+    #[test_case("  na Player#abc  ", LeagueRegion::NA, "player", "ABC", None ; "sub")]
+    #[test_case("KR Name#With#Hash#kr1 Support", LeagueRegion::KR, "name#with#hash", "KR1", Some(Role::Support) ; "hash in game name")]
+    fn test_parse(
+        line: &str,
+        region: LeagueRegion,
+        game_name: &str,
+        tag_line: &str,
+        role: Option<Role>,
+    ) {
+        let member = RosterMember::parse(line).unwrap();
+        assert_eq!(member.player.region, region);
+        assert_eq!(member.player.game_name, game_name);
+        assert_eq!(member.player.tag_line, tag_line);
+        assert_eq!(member.role, role);
+    }
+
+    #[test_case("EUW" ; "only a region")]
+    #[test_case("XX Name#1234" ; "unknown region")]
+    #[test_case("EUW Name" ; "no tag line")]
+    #[test_case("EUW Name# " ; "empty tag line")]
+    #[test_case("EUW Name#1234 coach" ; "unknown role")]
+    fn test_parse_error(line: &str) {
+        assert!(RosterMember::parse(line).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use crate::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::PathBuf,
+    sync::{Mutex, MutexGuard},
+};
+
+pub fn data_dir() -> PathBuf {
+    env::var("DATA_DIR")
+        .unwrap_or_else(|_| "data".to_string())
+        .into()
+}
+
+/// A small collection of items, keyed by ID, that is kept in memory and
+/// written to a JSON file in the data directory whenever it changes.
+pub struct JsonStore<T> {
+    path: PathBuf,
+    items: Mutex<BTreeMap<String, T>>,
+}
+
+impl<T: Clone + DeserializeOwned + Serialize> JsonStore<T> {
+    /// Loads `{DATA_DIR}/{name}.json`, or starts empty if it doesn't exist.
+    pub fn load(name: &str) -> Result<Self> {
+        Self::load_path(data_dir().join(format!("{name}.json")))
+    }
+
+    /// Loads the store kept at `path`, or starts empty if it doesn't exist.
+    pub fn load_path(path: PathBuf) -> Result<Self> {
+        let items = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path,
+            items: Mutex::new(items),
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<String, T>> {
+        // Nothing we do while holding the lock can leave the map in a bad
+        // state, so a poisoned lock is fine to keep using.
+        self.items
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn save(&self, items: &BTreeMap<String, T>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write to a temporary file first, so we never leave a half-written
+        // file behind.
+        let temp_path = self.path.with_extension("json.tmp");
+        fs::write(&temp_path, serde_json::to_string_pretty(items)?)?;
+        fs::rename(temp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn get(&self, id: &str) -> Option<T> {
+        self.lock().get(id).cloned()
+    }
+
+    /// All items, sorted by ID.
+    pub fn list(&self) -> Vec<(String, T)> {
+        self.lock()
+            .iter()
+            .map(|(id, item)| (id.clone(), item.clone()))
+            .collect()
+    }
+
+    pub fn insert(&self, id: String, item: T) -> Result<()> {
+        let mut items = self.lock();
+        items.insert(id, item);
+        self.save(&items)
+    }
+
+    /// Inserts under an ID derived from `name` that isn't in use yet, and
+    /// returns that ID.
+    pub fn insert_new(&self, name: &str, item: T) -> Result<String> {
+        let mut items = self.lock();
+        let slug = slugify(name);
+        let mut id = slug.clone();
+        let mut suffix = 2;
+        while items.contains_key(&id) {
+            id = format!("{slug}-{suffix}");
+            suffix += 1;
+        }
+        items.insert(id.clone(), item);
+        self.save(&items)?;
+        Ok(id)
+    }
+
+    pub fn remove(&self, id: &str) -> Result<Option<T>> {
+        let mut items = self.lock();
+        let removed = items.remove(id);
+        if removed.is_some() {
+            self.save(&items)?;
+        }
+        Ok(removed)
+    }
+}
+
+/// Lowercase ASCII letters and digits, with runs of anything else turned
+/// into a single dash.
+fn slugify(name: &str) -> String {
+    let slug = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "unnamed".to_string()
+    } else {
+        slug
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{slugify, JsonStore};
+    use std::{env, fs, path::PathBuf};
+    use test_case::test_case;
+
+    #[test_case("Team Liquid", "team-liquid" ; "spaces")]
+    #[test_case("  G2 -- Esports!  ", "g2-esports" ; "runs of punctuation")]
+    #[test_case("Ünïcode Squad", "n-code-squad" ; "non ascii")]
+    #[test_case("???", "unnamed" ; "only punctuation")]
+    #[test_case("", "unnamed" ; "empty")]
+    fn test_slugify(name: &str, expected: &str) {
+        assert_eq!(slugify(name), expected);
+    }
+
+    /// A fresh file per test, so tests running at the same time don't share
+    /// a store.
+    fn temp_path(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("lol-performance-review-{}", std::process::id()));
+        let path = dir.join(format!("{test}.json"));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_insert_new_avoids_collisions() {
+        let store = JsonStore::<u32>::load_path(temp_path("collisions")).unwrap();
+        assert_eq!(store.insert_new("My Team", 1).unwrap(), "my-team");
+        assert_eq!(store.insert_new("my team", 2).unwrap(), "my-team-2");
+        assert_eq!(store.insert_new("MY_TEAM", 3).unwrap(), "my-team-3");
+        assert_eq!(store.get("my-team-2"), Some(2));
+    }
+
+    #[test]
+    fn test_changes_are_saved() {
+        let path = temp_path("saved");
+        let store = JsonStore::<String>::load_path(path.clone()).unwrap();
+        store.insert("a".to_string(), "first".to_string()).unwrap();
+        store.insert("b".to_string(), "second".to_string()).unwrap();
+        assert_eq!(store.remove("a").unwrap(), Some("first".to_string()));
+        assert_eq!(store.remove("a").unwrap(), None);
+
+        let reloaded = JsonStore::<String>::load_path(path).unwrap();
+        assert_eq!(
+            reloaded.list(),
+            vec![("b".to_string(), "second".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_load_missing_file_is_empty() {
+        let store = JsonStore::<u32>::load_path(temp_path("missing")).unwrap();
+        assert!(store.list().is_empty());
+    }
+}